@@ -0,0 +1,58 @@
+#![feature(test, allocator_api)]
+
+extern crate test;
+
+use alloc_compose::{Region, ReverseRegion};
+use core::alloc::{AllocInit, AllocRef, Layout};
+use test::{black_box, Bencher};
+
+const ITERATIONS: usize = 64;
+
+fn layouts() -> [Layout; 4] {
+    [
+        Layout::new::<u8>(),
+        Layout::new::<u64>(),
+        Layout::new::<[u16; 3]>(),
+        Layout::new::<[u128; 2]>(),
+    ]
+}
+
+macro_rules! bench_region {
+    ($region:ident, $alloc:ident, $alloc_dealloc:ident) => {
+        #[bench]
+        fn $alloc(b: &mut Bencher) {
+            let mut data = [0; 4096];
+            let mut region = $region::new(&mut data);
+            let layouts = layouts();
+            b.iter(|| {
+                for i in 0..ITERATIONS {
+                    black_box(region.alloc(layouts[i % layouts.len()], AllocInit::Uninitialized))
+                        .expect("Could not allocate memory");
+                }
+                region.reset();
+            });
+        }
+
+        #[bench]
+        fn $alloc_dealloc(b: &mut Bencher) {
+            let mut data = [0; 4096];
+            let mut region = $region::new(&mut data);
+            let layouts = layouts();
+            b.iter(|| {
+                for i in 0..ITERATIONS {
+                    let layout = layouts[i % layouts.len()];
+                    let memory = black_box(region.alloc(layout, AllocInit::Uninitialized))
+                        .expect("Could not allocate memory");
+                    unsafe { region.dealloc(memory.ptr, layout) };
+                }
+            });
+        }
+    };
+}
+
+bench_region!(Region, region_alloc, region_alloc_dealloc);
+bench_region!(
+    ReverseRegion,
+    reverse_region_alloc,
+    reverse_region_alloc_dealloc
+);
@@ -1,4 +1,4 @@
-use crate::Result;
+use crate::{Owns, Result};
 use core::{
     alloc::{AllocErr, AllocInit, AllocRef, Layout, LayoutErr, MemoryBlock, ReallocPlacement},
     marker::PhantomData,
@@ -49,6 +49,32 @@ impl<Alloc, Prefix, Suffix> Affix<Alloc, Prefix, Suffix> {
         NonNull::new_unchecked(ptr.as_ptr().add(offset)).cast()
     }
 
+    /// Returns a mutable reference to the prefix of the block at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// * `ptr` must denote a block of memory [*currently allocated*] via this allocator,
+    /// * `layout` must [*fit*] that block of memory,
+    /// * the prefix must have been initialized, and
+    /// * the returned reference must not outlive the block of memory or alias another reference
+    ///   to the prefix.
+    pub unsafe fn prefix_mut<'a>(ptr: NonNull<u8>, layout: Layout) -> &'a mut Prefix {
+        &mut *Self::prefix(ptr, layout).as_ptr()
+    }
+
+    /// Returns a mutable reference to the suffix of the block at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// * `ptr` must denote a block of memory [*currently allocated*] via this allocator,
+    /// * `layout` must [*fit*] that block of memory,
+    /// * the suffix must have been initialized, and
+    /// * the returned reference must not outlive the block of memory or alias another reference
+    ///   to the suffix.
+    pub unsafe fn suffix_mut<'a>(ptr: NonNull<u8>, layout: Layout) -> &'a mut Suffix {
+        &mut *Self::suffix(ptr, layout).as_ptr()
+    }
+
     fn extend_layout(layout: Layout) -> Result<(Layout, usize, usize), LayoutErr> {
         let prefix_layout = Layout::new::<Prefix>();
         let suffix_layout = Layout::new::<Suffix>();
@@ -166,13 +192,40 @@ where
     }
 }
 
+impl<Alloc, Prefix, Suffix> Owns for Affix<Alloc, Prefix, Suffix>
+where
+    Alloc: Owns,
+{
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        // The alignment of `memory` is unknown. Assuming the smallest possible alignment yields
+        // the smallest block around `memory`, which is contained in the underlying block.
+        let layout = match Layout::from_size_align(memory.size, 1) {
+            Ok(layout) => layout,
+            Err(_) => return false,
+        };
+        let (layout, prefix_offset) = match Self::extend_layout(layout) {
+            Ok((layout, prefix_offset, _)) => (layout, prefix_offset),
+            Err(_) => return false,
+        };
+        let ptr = (memory.ptr.as_ptr() as usize).wrapping_sub(prefix_offset) as *mut u8;
+        match NonNull::new(ptr) {
+            Some(ptr) => self.alloc.owns(MemoryBlock {
+                ptr,
+                size: layout.size(),
+            }),
+            None => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::wildcard_imports)]
     use super::*;
     use crate::{
-        helper::{AsSlice, Tracker},
-        Proxy,
+        helper::{self, AsSlice},
+        FallbackAlloc,
+        Region,
     };
     use core::fmt;
     use std::alloc::System;
@@ -184,10 +237,7 @@ mod tests {
         Suffix: fmt::Debug + Copy + PartialEq,
     {
         unsafe {
-            let mut alloc = Proxy {
-                alloc: Affix::<System, Prefix, Suffix>::default(),
-                callbacks: Tracker::default(),
-            };
+            let mut alloc = helper::tracker(Affix::<System, Prefix, Suffix>::default());
             let memory = alloc
                 .alloc(layout, AllocInit::Zeroed)
                 .unwrap_or_else(|_| panic!("Could not allocate {} bytes", layout.size()));
@@ -308,4 +358,53 @@ mod tests {
     fn test_alloc_u32_u64_a16() {
         test_alloc::<u32, AlignTo16>(0xDEDE_DEDE, Layout::new::<u64>(), AlignTo16)
     }
+
+    #[test]
+    fn owns() {
+        let mut data = [0; 64];
+        let mut alloc = FallbackAlloc {
+            primary: Affix::<_, u32, u16>::new(Region::new(&mut data)),
+            fallback: System,
+        };
+        let small_layout = Layout::new::<[u8; 8]>();
+        let big_layout = Layout::new::<[u8; 64]>();
+
+        let small = alloc
+            .alloc(small_layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let big = alloc
+            .alloc(big_layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 64 bytes");
+        assert!(alloc.primary.owns(small));
+        assert!(!alloc.primary.owns(big));
+
+        unsafe {
+            alloc.dealloc(big.ptr, big_layout);
+            alloc.dealloc(small.ptr, small_layout);
+        }
+        assert!(!alloc.primary.owns(small));
+    }
+
+    #[test]
+    fn prefix_suffix_mut() {
+        type Alloc = Affix<System, u32, u16>;
+
+        let mut alloc = Alloc::default();
+        let layout = Layout::new::<u64>();
+        let memory = alloc
+            .alloc(layout, AllocInit::Zeroed)
+            .expect("Could not allocate 8 bytes");
+
+        unsafe {
+            *Alloc::prefix_mut(memory.ptr, layout) = 0xDEDE_DEDE;
+            *Alloc::suffix_mut(memory.ptr, layout) = 0xEFEF;
+            *Alloc::prefix_mut(memory.ptr, layout) += 1;
+
+            assert_eq!(Alloc::prefix(memory.ptr, layout).as_ref(), &0xDEDE_DEDF);
+            assert_eq!(Alloc::suffix(memory.ptr, layout).as_ref(), &0xEFEF);
+            assert_eq!(memory.as_slice(), &[0; 8][..]);
+
+            alloc.dealloc(memory.ptr, layout);
+        }
+    }
 }
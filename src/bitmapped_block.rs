@@ -0,0 +1,516 @@
+use crate::Owns;
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    fmt,
+    mem,
+    ptr::{self, NonNull},
+    slice,
+};
+
+const BITS: usize = mem::size_of::<usize>() * 8;
+
+/// Allocator over an user-defined region of memory, which is divided into blocks of `BLOCK_SIZE`.
+///
+/// The occupancy of the blocks is tracked in a bitmap, which is stored at the end of the passed
+/// region. Allocations are rounded up to a multiple of `BLOCK_SIZE` and placed at the first
+/// sufficient range of free blocks. In contrast to [`Region`], every block can be deallocated, and
+/// blocks may be grown in place as long as the adjacent blocks are free.
+///
+/// [`Region`]: crate::Region
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{BitmappedBlock, Owns};
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+///
+/// let mut data = [0; 256];
+/// let mut alloc = BitmappedBlock::<32>::new(&mut data);
+///
+/// let first = alloc.alloc(Layout::new::<[u8; 20]>(), AllocInit::Uninitialized)?;
+/// let second = alloc.alloc(Layout::new::<[u8; 40]>(), AllocInit::Uninitialized)?;
+/// assert_eq!(first.size, 32);
+/// assert_eq!(second.size, 64);
+///
+/// // Deallocating is not restricted to the last block
+/// unsafe { alloc.dealloc(first.ptr, Layout::new::<[u8; 20]>()) };
+/// let third = alloc.alloc(Layout::new::<[u8; 32]>(), AllocInit::Uninitialized)?;
+/// assert_eq!(first.ptr, third.ptr);
+/// assert!(alloc.owns(third));
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct BitmappedBlock<'a, const BLOCK_SIZE: usize> {
+    bitmap: &'a mut [usize],
+    blocks: &'a mut [u8],
+    free: usize,
+}
+
+impl<'a, const BLOCK_SIZE: usize> BitmappedBlock<'a, BLOCK_SIZE> {
+    const fn assert_block_size() {
+        assert!(BLOCK_SIZE > 0, "BLOCK_SIZE must not be zero");
+    }
+
+    const fn bitmap_size(num_blocks: usize) -> usize {
+        (num_blocks + BITS - 1) / BITS * mem::size_of::<usize>()
+    }
+
+    /// Creates a new allocator over `data`.
+    ///
+    /// The beginning of `data` is divided into blocks of `BLOCK_SIZE`, the end of `data` is used
+    /// to store the bitmap.
+    pub fn new(data: &'a mut [u8]) -> Self {
+        Self::assert_block_size();
+
+        let start = data.as_ptr() as usize;
+        let end = start + data.len();
+        let fits = |num_blocks: usize| {
+            let bitmap = (start + num_blocks * BLOCK_SIZE + mem::align_of::<usize>() - 1)
+                & !(mem::align_of::<usize>() - 1);
+            bitmap + Self::bitmap_size(num_blocks) <= end
+        };
+        let mut num_blocks = data.len() / BLOCK_SIZE;
+        while num_blocks > 0 && !fits(num_blocks) {
+            num_blocks -= 1;
+        }
+
+        let (blocks, bitmap) = data.split_at_mut(num_blocks * BLOCK_SIZE);
+        let bitmap: &mut [usize] = if num_blocks == 0 {
+            // The remaining bytes may not be aligned for `usize`
+            &mut []
+        } else {
+            let offset = bitmap.as_ptr().align_offset(mem::align_of::<usize>());
+            unsafe {
+                slice::from_raw_parts_mut(
+                    bitmap[offset..].as_mut_ptr().cast::<usize>(),
+                    Self::bitmap_size(num_blocks) / mem::size_of::<usize>(),
+                )
+            }
+        };
+        for word in bitmap.iter_mut() {
+            *word = 0;
+        }
+
+        Self {
+            bitmap,
+            blocks,
+            free: num_blocks,
+        }
+    }
+
+    /// Returns the number of blocks managed by this allocator.
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len() / BLOCK_SIZE
+    }
+
+    /// Returns the total capacity available in this allocator.
+    pub fn capacity(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns the free capacity left for allocating.
+    ///
+    /// Note, that the free capacity may be fragmented.
+    pub fn capacity_left(&self) -> usize {
+        self.free * BLOCK_SIZE
+    }
+
+    /// Resets the allocator by marking all blocks as free.
+    ///
+    /// This implies, that `capacity()` and `capacity_left()` are equal afterwards.
+    pub fn reset(&mut self) {
+        for word in self.bitmap.iter_mut() {
+            *word = 0;
+        }
+        self.free = self.num_blocks();
+        debug_assert_eq!(self.capacity(), self.capacity_left());
+    }
+
+    const fn blocks_for(size: usize) -> usize {
+        if size == 0 {
+            1
+        } else {
+            (size + BLOCK_SIZE - 1) / BLOCK_SIZE
+        }
+    }
+
+    fn block_ptr(&self, index: usize) -> NonNull<u8> {
+        unsafe { NonNull::new_unchecked(self.blocks.as_ptr().add(index * BLOCK_SIZE) as *mut u8) }
+    }
+
+    fn block_index(&self, ptr: NonNull<u8>) -> usize {
+        let offset = ptr.as_ptr() as usize - self.blocks.as_ptr() as usize;
+        debug_assert_eq!(
+            offset % BLOCK_SIZE,
+            0,
+            "`ptr` must denote a block of memory currently allocated via this allocator"
+        );
+        offset / BLOCK_SIZE
+    }
+
+    fn is_free(&self, index: usize) -> bool {
+        self.bitmap[index / BITS] & (1 << (index % BITS)) == 0
+    }
+
+    /// Returns the index of the first used block in `start..start + count` or `None` if all
+    /// blocks are free.
+    fn find_used(&self, start: usize, count: usize) -> Option<usize> {
+        (start..start + count).find(|&i| !self.is_free(i))
+    }
+
+    /// Marks `start..start + count` as used or free. The blocks must be in the opposite state.
+    fn mark(&mut self, start: usize, count: usize, used: bool) {
+        for i in start..start + count {
+            if used {
+                self.bitmap[i / BITS] |= 1 << (i % BITS);
+            } else {
+                self.bitmap[i / BITS] &= !(1 << (i % BITS));
+            }
+        }
+        if used {
+            self.free -= count;
+        } else {
+            self.free += count;
+        }
+    }
+}
+
+impl<const BLOCK_SIZE: usize> fmt::Debug for BitmappedBlock<'_, BLOCK_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BitmappedBlock")
+            .field("capacity", &self.capacity())
+            .field("capacity_left", &self.capacity_left())
+            .finish()
+    }
+}
+
+unsafe impl<const BLOCK_SIZE: usize> AllocRef for BitmappedBlock<'_, BLOCK_SIZE> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        let count = Self::blocks_for(layout.size());
+        let num_blocks = self.num_blocks();
+        if count > self.free {
+            return Err(AllocErr);
+        }
+
+        // Aligned blocks repeat every `step` blocks, starting at `first`
+        let step = layout.align() / layout.align().min(1 << BLOCK_SIZE.trailing_zeros());
+        let first = (0..step.min(num_blocks))
+            .find(|&i| self.block_ptr(i).as_ptr() as usize % layout.align() == 0)
+            .ok_or(AllocErr)?;
+        let next_aligned =
+            |index: usize| first + (index.saturating_sub(first) + step - 1) / step * step;
+
+        let mut start = first;
+        while start + count <= num_blocks {
+            if self.bitmap[start / BITS] == usize::MAX {
+                start = next_aligned((start / BITS + 1) * BITS);
+                continue;
+            }
+
+            if let Some(used) = self.find_used(start, count) {
+                start = next_aligned(used + 1);
+                continue;
+            }
+
+            let ptr = self.block_ptr(start);
+
+            self.mark(start, count, true);
+            let memory = MemoryBlock {
+                ptr,
+                size: count * BLOCK_SIZE,
+            };
+            unsafe { init.init(memory) };
+            return Ok(memory);
+        }
+
+        Err(AllocErr)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        debug_assert!(
+            self.owns(MemoryBlock {
+                ptr,
+                size: layout.size()
+            }),
+            "`ptr` must denote a block of memory currently allocated via this allocator"
+        );
+        let index = self.block_index(ptr);
+        self.mark(index, Self::blocks_for(layout.size()), false);
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size >= layout.size(),
+            "`new_size` must be greater than or equal to `layout.size()`"
+        );
+
+        let index = self.block_index(ptr);
+        let old_count = Self::blocks_for(layout.size());
+        let new_count = Self::blocks_for(new_size);
+
+        if index + new_count <= self.num_blocks()
+            && self
+                .find_used(index + old_count, new_count - old_count)
+                .is_none()
+        {
+            self.mark(index + old_count, new_count - old_count, true);
+            let memory = MemoryBlock {
+                ptr,
+                size: new_count * BLOCK_SIZE,
+            };
+            init.init_offset(memory, layout.size());
+            Ok(memory)
+        } else if placement == ReallocPlacement::MayMove {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_memory = self.alloc(new_layout, init)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), layout.size());
+            self.dealloc(ptr, layout);
+            Ok(new_memory)
+        } else {
+            Err(AllocErr)
+        }
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        _placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size <= layout.size(),
+            "`new_size` must be smaller than or equal to `layout.size()`"
+        );
+
+        let index = self.block_index(ptr);
+        let old_count = Self::blocks_for(layout.size());
+        let new_count = Self::blocks_for(new_size);
+        self.mark(index + new_count, old_count - new_count, false);
+
+        Ok(MemoryBlock {
+            ptr,
+            size: new_count * BLOCK_SIZE,
+        })
+    }
+}
+
+impl<const BLOCK_SIZE: usize> Owns for BitmappedBlock<'_, BLOCK_SIZE> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        let start = self.blocks.as_ptr() as usize;
+        let ptr = memory.ptr.as_ptr() as usize;
+        start <= ptr && ptr + memory.size <= start + self.blocks.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::helper::{self, AsSlice};
+    use std::alloc::{Global, Layout};
+
+    #[test]
+    fn new() {
+        let mut data = [1; 256];
+        let alloc = BitmappedBlock::<16>::new(&mut data);
+        assert_eq!(alloc.capacity(), alloc.capacity_left());
+        assert_eq!(alloc.capacity(), alloc.num_blocks() * 16);
+        assert!(alloc.capacity() >= 256 - 16 - 2 * mem::size_of::<usize>());
+
+        let mut data = [1; 5];
+        let mut alloc = BitmappedBlock::<16>::new(&mut data[1..]);
+        assert_eq!(alloc.num_blocks(), 0);
+        assert_eq!(alloc.capacity_left(), 0);
+        alloc
+            .alloc(Layout::new::<()>(), AllocInit::Uninitialized)
+            .expect_err("Could allocate without blocks");
+    }
+
+    #[test]
+    fn alloc() {
+        let mut data = [1; 256];
+        let mut alloc = BitmappedBlock::<16>::new(&mut data);
+        let num_blocks = alloc.num_blocks();
+
+        let memory = alloc
+            .alloc(Layout::new::<[u8; 20]>(), AllocInit::Zeroed)
+            .expect("Could not allocate 20 bytes");
+        assert_eq!(memory.size, 32);
+        assert!(alloc.owns(memory));
+        unsafe { assert_eq!(memory.as_slice(), &[0; 32][..]) };
+        assert_eq!(alloc.capacity_left(), (num_blocks - 2) * 16);
+
+        alloc
+            .alloc(
+                Layout::from_size_align(num_blocks * 16, 1).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect_err("Could allocate more blocks than available");
+
+        let memory = alloc
+            .alloc(Layout::new::<()>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 0 bytes");
+        assert_eq!(memory.size, 16);
+        assert_eq!(alloc.capacity_left(), (num_blocks - 3) * 16);
+
+        alloc.reset();
+        assert_eq!(alloc.capacity_left(), alloc.capacity());
+    }
+
+    #[test]
+    fn alloc_aligned() {
+        let memory = Global
+            .alloc(
+                Layout::from_size_align(1024, 64).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect("Could not allocate 1024 Bytes");
+        let mut alloc = BitmappedBlock::<16>::new(unsafe { memory.as_slice_mut() });
+
+        alloc
+            .alloc(Layout::new::<u8>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 1 byte");
+        let aligned = alloc
+            .alloc(
+                Layout::from_size_align(16, 64).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect("Could not allocate 16 bytes");
+        assert_eq!(aligned.ptr.as_ptr() as usize % 64, 0);
+
+        let aligned = alloc
+            .alloc(
+                Layout::from_size_align(16, 256).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect("Could not allocate 16 bytes");
+        assert_eq!(aligned.ptr.as_ptr() as usize % 256, 0);
+        assert_eq!(alloc.capacity_left(), alloc.capacity() - 3 * 16);
+
+        unsafe {
+            Global.dealloc(
+                memory.ptr,
+                Layout::from_size_align(1024, 64).expect("Invalid layout"),
+            )
+        };
+    }
+
+    #[test]
+    fn dealloc() {
+        let mut data = [1; 256];
+        let mut alloc = helper::tracker(BitmappedBlock::<16>::new(&mut data));
+        let layout = Layout::new::<[u8; 16]>();
+
+        let first = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+        let second = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+        let third = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+
+        unsafe { alloc.dealloc(second.ptr, layout) };
+        let memory = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+        assert_eq!(memory.ptr, second.ptr);
+
+        unsafe {
+            alloc.dealloc(first.ptr, layout);
+            alloc.dealloc(memory.ptr, layout);
+            alloc.dealloc(third.ptr, layout);
+        }
+    }
+
+    #[test]
+    fn grow() {
+        let mut data = [1; 256];
+        let mut alloc = BitmappedBlock::<16>::new(&mut data);
+        let layout = Layout::new::<[u8; 16]>();
+
+        unsafe {
+            let first = alloc
+                .alloc(layout, AllocInit::Zeroed)
+                .expect("Could not allocate 16 bytes");
+            let second = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 16 bytes");
+
+            alloc
+                .grow(
+                    first.ptr,
+                    layout,
+                    32,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 32 bytes in place");
+
+            let memory = alloc
+                .grow(
+                    second.ptr,
+                    layout,
+                    48,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 48 bytes");
+            assert_eq!(memory.ptr, second.ptr);
+            assert_eq!(memory.size, 48);
+            assert_eq!(&memory.as_slice()[16..], &[0; 32][..]);
+
+            first.as_slice_mut().copy_from_slice(&[2; 16]);
+            let memory = alloc
+                .grow(
+                    first.ptr,
+                    layout,
+                    32,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 32 bytes");
+            assert_ne!(memory.ptr, first.ptr);
+            assert_eq!(&memory.as_slice()[..16], &[2; 16][..]);
+            assert_eq!(&memory.as_slice()[16..], &[0; 16][..]);
+        }
+    }
+
+    #[test]
+    fn shrink() {
+        let mut data = [1; 256];
+        let mut alloc = BitmappedBlock::<16>::new(&mut data);
+        let num_blocks = alloc.num_blocks();
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 64]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 64 bytes");
+            assert_eq!(alloc.capacity_left(), (num_blocks - 4) * 16);
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 64]>(),
+                    20,
+                    ReallocPlacement::InPlace,
+                )
+                .expect("Could not shrink to 20 bytes");
+            assert_eq!(memory.size, 32);
+            assert_eq!(alloc.capacity_left(), (num_blocks - 2) * 16);
+
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 20]>());
+            assert_eq!(alloc.capacity_left(), alloc.capacity());
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{grow, shrink, Owns};
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    cmp,
+    ptr::NonNull,
+};
+
+/// Dispatches calls to `AllocRef` between an array of allocators depending on the size allocated.
+///
+/// The range `MIN..=MAX` is divided into `BUCKETS` buckets with a size of `STEP`. Every bucket is
+/// served by its own allocator: The allocator at index `i` is responsible for all sizes in
+/// `MIN + i * STEP..MIN + (i + 1) * STEP`. Requests outside of `MIN..=MAX` fail.
+///
+/// When growing or shrinking a block across the boundary of a bucket, the memory is moved to the
+/// allocator of the new bucket.
+///
+/// As long as `const_generics` doesn't support expressions, the number of buckets has to be passed
+/// explicitly. It must be equal to `(MAX - MIN + 1) / STEP`.
+///
+/// # Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{Bucketizer, Owns, Region};
+/// use std::alloc::{AllocInit, AllocRef, Layout};
+///
+/// let mut data = [[0; 64]; 4];
+/// let [a, b, c, d] = &mut data;
+/// let mut alloc = Bucketizer::<_, 1, 64, 16, 4> {
+///     buckets: [
+///         Region::new(a),
+///         Region::new(b),
+///         Region::new(c),
+///         Region::new(d),
+///     ],
+/// };
+///
+/// let memory = alloc.alloc(Layout::new::<[u8; 20]>(), AllocInit::Uninitialized)?;
+/// assert!(alloc.buckets[1].owns(memory));
+/// assert!(alloc.owns(memory));
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Bucketizer<
+    A,
+    const MIN: usize,
+    const MAX: usize,
+    const STEP: usize,
+    const BUCKETS: usize,
+> {
+    pub buckets: [A; BUCKETS],
+}
+
+impl<A, const MIN: usize, const MAX: usize, const STEP: usize, const BUCKETS: usize>
+    Bucketizer<A, MIN, MAX, STEP, BUCKETS>
+{
+    const fn assert_bounds() {
+        assert!(STEP > 0, "STEP must not be zero");
+        assert!(MIN <= MAX, "MIN must be smaller than or equal to MAX");
+        assert!(
+            (MAX - MIN) % STEP == STEP - 1,
+            "`MAX - MIN + 1` must be a multiple of STEP"
+        );
+        assert!(
+            (MAX - MIN) / STEP + 1 == BUCKETS,
+            "BUCKETS must be equal to `(MAX - MIN + 1) / STEP`"
+        );
+    }
+
+    fn bucket_index(size: usize) -> Option<usize> {
+        Self::assert_bounds();
+        if size < MIN || size > MAX {
+            None
+        } else {
+            Some((size - MIN) / STEP)
+        }
+    }
+
+    const fn bucket_max(index: usize) -> usize {
+        MIN + (index + 1) * STEP - 1
+    }
+
+    fn clamp_memory(memory: MemoryBlock, index: usize) -> MemoryBlock {
+        MemoryBlock {
+            ptr: memory.ptr,
+            size: cmp::min(memory.size, Self::bucket_max(index)),
+        }
+    }
+
+    fn buckets_mut(&mut self, a: usize, b: usize) -> (&mut A, &mut A) {
+        debug_assert_ne!(a, b);
+        if a < b {
+            let (left, right) = self.buckets.split_at_mut(b);
+            (&mut left[a], &mut right[0])
+        } else {
+            let (left, right) = self.buckets.split_at_mut(a);
+            (&mut right[0], &mut left[b])
+        }
+    }
+}
+
+unsafe impl<A, const MIN: usize, const MAX: usize, const STEP: usize, const BUCKETS: usize> AllocRef
+    for Bucketizer<A, MIN, MAX, STEP, BUCKETS>
+where
+    A: AllocRef,
+{
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        let index = Self::bucket_index(layout.size()).ok_or(AllocErr)?;
+        let memory = self.buckets[index].alloc(layout, init)?;
+        Ok(Self::clamp_memory(memory, index))
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        let index = Self::bucket_index(layout.size())
+            .expect("`layout` must fit a block of memory allocated via this allocator");
+        self.buckets[index].dealloc(ptr, layout)
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let old_index = Self::bucket_index(layout.size())
+            .expect("`layout` must fit a block of memory allocated via this allocator");
+        let new_index = Self::bucket_index(new_size).ok_or(AllocErr)?;
+
+        let memory = if old_index == new_index {
+            self.buckets[old_index].grow(ptr, layout, new_size, placement, init)?
+        } else {
+            let (old, new) = self.buckets_mut(old_index, new_index);
+            grow(old, new, ptr, layout, new_size, placement, init)?
+        };
+        Ok(Self::clamp_memory(memory, new_index))
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let old_index = Self::bucket_index(layout.size())
+            .expect("`layout` must fit a block of memory allocated via this allocator");
+        let new_index = Self::bucket_index(new_size).ok_or(AllocErr)?;
+
+        let memory = if old_index == new_index {
+            self.buckets[old_index].shrink(ptr, layout, new_size, placement)?
+        } else {
+            let (old, new) = self.buckets_mut(old_index, new_index);
+            shrink(old, new, ptr, layout, new_size, placement)?
+        };
+        Ok(Self::clamp_memory(memory, new_index))
+    }
+}
+
+impl<A, const MIN: usize, const MAX: usize, const STEP: usize, const BUCKETS: usize> Owns
+    for Bucketizer<A, MIN, MAX, STEP, BUCKETS>
+where
+    A: Owns,
+{
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.buckets.iter().any(|bucket| bucket.owns(memory))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bucketizer;
+    use crate::{
+        helper::{self, AsSlice},
+        stats::Counter,
+        CallbackRef,
+        Owns,
+        Proxy,
+        Region,
+    };
+    use std::alloc::{AllocInit, AllocRef, Layout, ReallocPlacement, System};
+
+    #[test]
+    fn alloc() {
+        let counters = [
+            Counter::default(),
+            Counter::default(),
+            Counter::default(),
+            Counter::default(),
+        ];
+        let mut alloc = helper::tracker(Bucketizer::<_, 1, 64, 16, 4> {
+            buckets: [
+                Proxy {
+                    alloc: System,
+                    callbacks: counters[0].by_ref(),
+                },
+                Proxy {
+                    alloc: System,
+                    callbacks: counters[1].by_ref(),
+                },
+                Proxy {
+                    alloc: System,
+                    callbacks: counters[2].by_ref(),
+                },
+                Proxy {
+                    alloc: System,
+                    callbacks: counters[3].by_ref(),
+                },
+            ],
+        });
+
+        for (size, index) in [(1, 0), (16, 0), (17, 1), (48, 2), (49, 3), (64, 3)].iter() {
+            let layout = Layout::from_size_align(*size, 1).expect("Invalid layout");
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .unwrap_or_else(|_| panic!("Could not allocate {} bytes", size));
+            unsafe { alloc.dealloc(memory.ptr, layout) };
+            assert_eq!(
+                counters[*index].num_allocs(),
+                counters[*index].num_deallocs()
+            );
+        }
+        assert_eq!(counters[0].num_allocs(), 2);
+        assert_eq!(counters[1].num_allocs(), 1);
+        assert_eq!(counters[2].num_allocs(), 1);
+        assert_eq!(counters[3].num_allocs(), 2);
+
+        alloc
+            .alloc(Layout::new::<()>(), AllocInit::Uninitialized)
+            .expect_err("Could allocate 0 bytes");
+        alloc
+            .alloc(Layout::new::<[u8; 65]>(), AllocInit::Uninitialized)
+            .expect_err("Could allocate 65 bytes");
+    }
+
+    #[test]
+    fn grow() {
+        let mut alloc = helper::tracker(Bucketizer::<_, 1, 64, 16, 4> {
+            buckets: [System; 4],
+        });
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Zeroed)
+                .expect("Could not allocate 8 bytes");
+            memory.as_slice_mut().copy_from_slice(&[1; 8]);
+
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 8]>(),
+                    40,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 40 bytes");
+            assert_eq!(memory.size, 40);
+            assert_eq!(&memory.as_slice()[..8], &[1; 8][..]);
+            assert_eq!(&memory.as_slice()[8..], &[0; 32][..]);
+
+            alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 40]>(),
+                    65,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 65 bytes");
+
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 40]>());
+        }
+    }
+
+    #[test]
+    fn shrink() {
+        let mut alloc = helper::tracker(Bucketizer::<_, 1, 64, 16, 4> {
+            buckets: [System; 4],
+        });
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 64]>(), AllocInit::Zeroed)
+                .expect("Could not allocate 64 bytes");
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 64]>(),
+                    8,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 8 bytes");
+            assert_eq!(memory.as_slice(), &[0; 8][..]);
+
+            alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 8]>(),
+                    0,
+                    ReallocPlacement::MayMove,
+                )
+                .expect_err("Could shrink to 0 bytes");
+
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 8]>());
+        }
+    }
+
+    #[test]
+    fn owns() {
+        let mut data = [[0; 16]; 2];
+        let [a, b] = &mut data;
+        let mut alloc = Bucketizer::<_, 1, 16, 8, 2> {
+            buckets: [Region::new(a), Region::new(b)],
+        };
+
+        let small = alloc
+            .alloc(Layout::new::<[u8; 4]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 4 bytes");
+        let large = alloc
+            .alloc(Layout::new::<[u8; 12]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 12 bytes");
+
+        assert!(alloc.buckets[0].owns(small));
+        assert!(alloc.buckets[1].owns(large));
+        assert!(alloc.owns(small));
+        assert!(alloc.owns(large));
+    }
+}
@@ -2,6 +2,7 @@
 use alloc::{boxed::Box, rc::Rc, sync::Arc};
 use core::{
     alloc::{AllocErr, AllocInit, Layout, MemoryBlock, ReallocPlacement},
+    panic::Location,
     ptr::NonNull,
 };
 
@@ -13,6 +14,10 @@ use core::{
 /// wrapping them into `Rc` or `Arc` in order to make them cloneable instead. Note, that
 /// `Box`, `Rc`, and `Arc` requires the `"alloc"`-feature to be enabled.
 ///
+/// Except for `owns`, every callback receives the location of the caller. As [`Proxy`] is
+/// annotated with `#[track_caller]`, this is the location where the method on `Proxy` was called
+/// unless the caller is annotated with `#[track_caller]` as well.
+///
 /// [`by_ref`]: CallbackRef::by_ref
 /// [`Proxy`]: crate::Proxy
 ///
@@ -22,16 +27,23 @@ pub unsafe trait CallbackRef {
     /// Called when [`alloc`] was invoked.
     ///
     /// [`alloc`]: core::alloc::AllocRef::alloc
-    fn alloc(&self, layout: Layout, init: AllocInit, result: Result<MemoryBlock, AllocErr>);
+    fn alloc(
+        &self,
+        layout: Layout,
+        init: AllocInit,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    );
 
     /// Called when [`dealloc`] was invoked.
     ///
     /// [`dealloc`]: core::alloc::AllocRef::dealloc
-    fn dealloc(&self, ptr: NonNull<u8>, layout: Layout);
+    fn dealloc(&self, ptr: NonNull<u8>, layout: Layout, location: &'static Location<'static>);
 
     /// Called when [`grow`] was invoked.
     ///
     /// [`grow`]: core::alloc::AllocRef::grow
+    #[allow(clippy::too_many_arguments)]
     fn grow(
         &self,
         ptr: NonNull<u8>,
@@ -40,6 +52,7 @@ pub unsafe trait CallbackRef {
         placement: ReallocPlacement,
         init: AllocInit,
         result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
     );
 
     /// Called when [`shrink`] was invoked.
@@ -52,6 +65,7 @@ pub unsafe trait CallbackRef {
         new_size: usize,
         placement: ReallocPlacement,
         result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
     );
 
     /// Called when [`owns`] was invoked.
@@ -70,13 +84,19 @@ pub unsafe trait CallbackRef {
 
 unsafe impl<C: CallbackRef> CallbackRef for &C {
     #[inline]
-    fn alloc(&self, layout: Layout, init: AllocInit, result: Result<MemoryBlock, AllocErr>) {
-        (**self).alloc(layout, init, result)
+    fn alloc(
+        &self,
+        layout: Layout,
+        init: AllocInit,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        (**self).alloc(layout, init, result, location)
     }
 
     #[inline]
-    fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
-        (**self).dealloc(ptr, layout)
+    fn dealloc(&self, ptr: NonNull<u8>, layout: Layout, location: &'static Location<'static>) {
+        (**self).dealloc(ptr, layout, location)
     }
 
     #[inline]
@@ -88,8 +108,9 @@ unsafe impl<C: CallbackRef> CallbackRef for &C {
         placement: ReallocPlacement,
         init: AllocInit,
         result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
     ) {
-        (**self).grow(ptr, layout, new_size, placement, init, result)
+        (**self).grow(ptr, layout, new_size, placement, init, result, location)
     }
 
     #[inline]
@@ -100,8 +121,9 @@ unsafe impl<C: CallbackRef> CallbackRef for &C {
         new_size: usize,
         placement: ReallocPlacement,
         result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
     ) {
-        (**self).shrink(ptr, layout, new_size, placement, result)
+        (**self).shrink(ptr, layout, new_size, placement, result, location)
     }
     #[inline]
     fn owns(&self, success: bool) {
@@ -121,13 +143,19 @@ macro_rules! impl_alloc_stats {
                 layout: Layout,
                 init: AllocInit,
                 result: Result<MemoryBlock, AllocErr>,
+                location: &'static Location<'static>,
             ) {
-                (**self).alloc(layout, init, result)
+                (**self).alloc(layout, init, result, location)
             }
 
             #[inline]
-            fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
-                (**self).dealloc(ptr, layout)
+            fn dealloc(
+                &self,
+                ptr: NonNull<u8>,
+                layout: Layout,
+                location: &'static Location<'static>,
+            ) {
+                (**self).dealloc(ptr, layout, location)
             }
 
             #[inline]
@@ -139,8 +167,9 @@ macro_rules! impl_alloc_stats {
                 placement: ReallocPlacement,
                 init: AllocInit,
                 result: Result<MemoryBlock, AllocErr>,
+                location: &'static Location<'static>,
             ) {
-                (**self).grow(ptr, layout, new_size, placement, init, result)
+                (**self).grow(ptr, layout, new_size, placement, init, result, location)
             }
 
             #[inline]
@@ -151,8 +180,9 @@ macro_rules! impl_alloc_stats {
                 new_size: usize,
                 placement: ReallocPlacement,
                 result: Result<MemoryBlock, AllocErr>,
+                location: &'static Location<'static>,
             ) {
-                (**self).shrink(ptr, layout, new_size, placement, result)
+                (**self).shrink(ptr, layout, new_size, placement, result, location)
             }
 
             #[inline]
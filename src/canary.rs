@@ -0,0 +1,393 @@
+use crate::{Affix, Owns, Result};
+use core::{
+    alloc::{AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    fmt,
+    mem,
+    ops::Range,
+    ptr::{self, NonNull},
+};
+
+/// The guard word of a [`Canary`] block, which was overwritten.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CanarySide {
+    /// The guard in front of the block was overwritten, e.g. by a buffer underflow.
+    Before,
+    /// The guard behind the block was overwritten, e.g. by a buffer overflow.
+    After,
+}
+
+/// Function called by [`Canary`], when a corrupted guard word was detected.
+///
+/// The arguments are the pointer and the layout of the block, and the side of the block, where
+/// the corruption was detected.
+pub type CanaryHandler = fn(NonNull<u8>, Layout, CanarySide);
+
+fn panic_on_corruption(ptr: NonNull<u8>, layout: Layout, side: CanarySide) {
+    match side {
+        CanarySide::Before => panic!(
+            "Canary in front of the block at {:?} with {:?} was overwritten",
+            ptr, layout
+        ),
+        CanarySide::After => panic!(
+            "Canary behind the block at {:?} with {:?} was overwritten",
+            ptr, layout
+        ),
+    }
+}
+
+/// Surrounds every block with guard words to detect buffer overflows and underflows.
+///
+/// On allocation, `PATTERN` is written in front of and behind the block. The guards also cover
+/// the padding between the guard words and the block, so writing a single byte out of bounds is
+/// detected regardless of the size and the alignment of the block. The returned block has exactly
+/// the requested size. The guards are verified in `dealloc`, `grow`, and `shrink`. If one of them
+/// was overwritten, the handler is called. By default, the handler panics.
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::Canary;
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+/// use std::alloc::System;
+///
+/// let mut alloc = Canary::<_, 0xDEAD_BEEF_DEAD_BEEF>::new(System);
+/// let memory = alloc.alloc(Layout::new::<[u8; 8]>(), AllocInit::Zeroed)?;
+///
+/// // Write one byte past the end of the block
+/// unsafe { memory.ptr.as_ptr().add(8).write(0) };
+///
+/// let result = std::panic::catch_unwind(move || unsafe {
+///     alloc.dealloc(memory.ptr, Layout::new::<[u8; 8]>())
+/// });
+/// assert!(result.is_err());
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+///
+/// The handler may also abort the process or only report the corruption:
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::Canary;
+/// use std::alloc::System;
+/// use std::process;
+///
+/// let alloc =
+///     Canary::<_, 0xDEAD_BEEF_DEAD_BEEF>::with_handler(System, |_, _, _| process::abort());
+/// ```
+pub struct Canary<A, const PATTERN: u64> {
+    alloc: Affix<A, u64, u64>,
+    handler: CanaryHandler,
+}
+
+impl<A, const PATTERN: u64> Canary<A, PATTERN> {
+    /// Creates a new `Canary`, which panics when a corruption is detected.
+    pub const fn new(alloc: A) -> Self {
+        Self::with_handler(alloc, panic_on_corruption)
+    }
+
+    /// Creates a new `Canary`, which calls `handler` when a corruption is detected.
+    pub const fn with_handler(alloc: A, handler: CanaryHandler) -> Self {
+        Self {
+            alloc: Affix::new(alloc),
+            handler,
+        }
+    }
+
+    /// Returns a reference to the parent allocator.
+    pub const fn parent(&self) -> &A {
+        &self.alloc.alloc
+    }
+
+    /// Returns the byte of the pattern at `address`, so aligned guard words equal `PATTERN`.
+    fn pattern_byte(address: usize) -> u8 {
+        PATTERN.to_ne_bytes()[address % mem::size_of::<u64>()]
+    }
+
+    /// Returns the ranges of the guard in front of and behind the block including the padding.
+    unsafe fn guards(ptr: NonNull<u8>, layout: Layout) -> [(CanarySide, Range<usize>); 2] {
+        let prefix = Affix::<A, u64, u64>::prefix(ptr, layout).as_ptr() as usize;
+        let suffix = Affix::<A, u64, u64>::suffix(ptr, layout).as_ptr() as usize;
+        let start = ptr.as_ptr() as usize;
+        [
+            (CanarySide::Before, prefix..start),
+            (
+                CanarySide::After,
+                start + layout.size()..suffix + mem::size_of::<u64>(),
+            ),
+        ]
+    }
+
+    unsafe fn write_guards(ptr: NonNull<u8>, layout: Layout) {
+        for (_, guard) in Self::guards(ptr, layout).iter().cloned() {
+            for address in guard {
+                (address as *mut u8).write(Self::pattern_byte(address));
+            }
+        }
+    }
+
+    unsafe fn verify_guards(&self, ptr: NonNull<u8>, layout: Layout) {
+        for (side, guard) in Self::guards(ptr, layout).iter().cloned() {
+            if guard
+                .into_iter()
+                .any(|address| *(address as *const u8) != Self::pattern_byte(address))
+            {
+                (self.handler)(ptr, layout, side);
+            }
+        }
+    }
+}
+
+impl<A: Default, const PATTERN: u64> Default for Canary<A, PATTERN> {
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+impl<A: fmt::Debug, const PATTERN: u64> fmt::Debug for Canary<A, PATTERN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Canary")
+            .field("alloc", &self.alloc.alloc)
+            .field("pattern", &PATTERN)
+            .finish()
+    }
+}
+
+unsafe impl<A: AllocRef, const PATTERN: u64> AllocRef for Canary<A, PATTERN> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result {
+        let memory = self.alloc.alloc(layout, init)?;
+        unsafe { Self::write_guards(memory.ptr, layout) };
+        Ok(MemoryBlock {
+            ptr: memory.ptr,
+            size: layout.size(),
+        })
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        self.verify_guards(ptr, layout);
+        self.alloc.dealloc(ptr, layout)
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result {
+        self.verify_guards(ptr, layout);
+        let memory = self.alloc.grow(ptr, layout, new_size, placement, init)?;
+        if init == AllocInit::Zeroed {
+            // The padding behind the old block still contains the pattern
+            let padding = layout.padding_needed_for(mem::align_of::<u64>());
+            ptr::write_bytes(memory.ptr.as_ptr().add(layout.size()), 0, padding);
+        }
+        Self::write_guards(
+            memory.ptr,
+            Layout::from_size_align_unchecked(new_size, layout.align()),
+        );
+        Ok(MemoryBlock {
+            ptr: memory.ptr,
+            size: new_size,
+        })
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result {
+        self.verify_guards(ptr, layout);
+        let memory = self.alloc.shrink(ptr, layout, new_size, placement)?;
+        Self::write_guards(
+            memory.ptr,
+            Layout::from_size_align_unchecked(new_size, layout.align()),
+        );
+        Ok(MemoryBlock {
+            ptr: memory.ptr,
+            size: new_size,
+        })
+    }
+}
+
+impl<A: Owns, const PATTERN: u64> Owns for Canary<A, PATTERN> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.alloc.owns(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::helper::{self, AsSlice};
+    use core::cell::Cell;
+    use std::alloc::System;
+
+    const PATTERN: u64 = 0xDEAD_BEEF_DEAD_BEEF;
+
+    thread_local! {
+        static CORRUPTED: Cell<Option<CanarySide>> = Cell::new(None);
+    }
+
+    fn record(_ptr: NonNull<u8>, _layout: Layout, side: CanarySide) {
+        CORRUPTED.with(|corrupted| corrupted.set(Some(side)));
+    }
+
+    fn take_corrupted() -> Option<CanarySide> {
+        CORRUPTED.with(Cell::take)
+    }
+
+    #[test]
+    fn intact() {
+        let mut alloc = Canary::<_, PATTERN>::with_handler(helper::tracker(System), record);
+        let layout = Layout::new::<[u8; 8]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Zeroed)
+                .expect("Could not allocate 8 bytes");
+            memory.ptr.as_ptr().write_bytes(0xFF, 8);
+
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 16 bytes");
+            memory.ptr.as_ptr().write_bytes(0xFF, 16);
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    4,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 4 bytes");
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 4]>());
+        }
+        assert_eq!(take_corrupted(), None);
+    }
+
+    #[test]
+    fn underflow() {
+        let mut alloc = Canary::<_, PATTERN>::with_handler(helper::tracker(System), record);
+        let layout = Layout::new::<[u8; 8]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.ptr.as_ptr().sub(1).write(0);
+            alloc.dealloc(memory.ptr, layout);
+        }
+        assert_eq!(take_corrupted(), Some(CanarySide::Before));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut alloc = Canary::<_, PATTERN>::with_handler(helper::tracker(System), record);
+        let layout = Layout::new::<[u8; 8]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.ptr.as_ptr().add(8).write(0);
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(take_corrupted(), Some(CanarySide::After));
+
+            // The guards are restored after growing
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 16]>());
+        }
+        assert_eq!(take_corrupted(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Canary behind the block")]
+    fn overflow_panic() {
+        let mut alloc = Canary::<_, PATTERN>::new(System);
+        let layout = Layout::new::<[u8; 8]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.ptr.as_ptr().add(8).write(0);
+            alloc.dealloc(memory.ptr, layout);
+        }
+    }
+
+    #[test]
+    fn off_by_one() {
+        let mut alloc = Canary::<_, PATTERN>::with_handler(helper::tracker(System), record);
+        let layout = Layout::new::<[u8; 5]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 5 bytes");
+            assert_eq!(memory.size, 5);
+            memory.ptr.as_ptr().add(5).write(0);
+            alloc.dealloc(memory.ptr, layout);
+        }
+        assert_eq!(take_corrupted(), Some(CanarySide::After));
+    }
+
+    #[test]
+    fn underflow_aligned() {
+        let mut alloc = Canary::<_, PATTERN>::with_handler(helper::tracker(System), record);
+        let layout = Layout::from_size_align(32, 32).expect("Invalid layout");
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 32 bytes");
+            assert_eq!(memory.ptr.as_ptr() as usize % 32, 0);
+            memory.ptr.as_ptr().sub(1).write(0);
+            alloc.dealloc(memory.ptr, layout);
+        }
+        assert_eq!(take_corrupted(), Some(CanarySide::Before));
+    }
+
+    #[test]
+    fn grow_zeroed() {
+        let mut alloc = Canary::<_, PATTERN>::with_handler(helper::tracker(System), record);
+        let layout = Layout::new::<[u8; 5]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Zeroed)
+                .expect("Could not allocate 5 bytes");
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    20,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 20 bytes");
+            assert_eq!(memory.as_slice(), &[0; 20][..]);
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 20]>());
+        }
+        assert_eq!(take_corrupted(), None);
+    }
+}
@@ -0,0 +1,509 @@
+use crate::Owns;
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    cmp,
+    fmt,
+    mem,
+    ptr::{self, NonNull},
+};
+
+struct Node {
+    next: Option<NonNull<Node>>,
+}
+
+/// Recycles deallocated blocks of memory with a size between `MIN` and `MAX`.
+///
+/// All allocations with a size in `MIN..=MAX` are served with a block of `MAX` bytes. When such a
+/// block is deallocated, it's not returned to the parent allocator but put into an intrusive
+/// singly linked list. Subsequent allocations in the same range are served from that list before
+/// asking the parent allocator for new memory. All other requests are forwarded to the parent.
+/// Blocks returned for requests smaller than `MIN` are reported with at most `MIN - 1` bytes, so
+/// they are never deallocated with a size in range.
+///
+/// Requests with an alignment greater than the alignment of a pointer are never served from the
+/// list. `MAX` must be at least the size of a pointer, so a freed block can hold the link to the
+/// next one.
+///
+/// Optionally, the length of the list can be limited with [`with_max_len`]. Blocks which doesn't
+/// fit into the list anymore are deallocated with the parent allocator. When the `FreeList` is
+/// dropped, all blocks in the list are returned to the parent.
+///
+/// [`with_max_len`]: Self::with_max_len
+///
+/// # Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::FreeList;
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let mut alloc = FreeList::<_, 16, 32>::new(System);
+///
+/// let memory = alloc.alloc(Layout::new::<[u8; 24]>(), AllocInit::Uninitialized)?;
+/// assert_eq!(memory.size, 32);
+/// unsafe { alloc.dealloc(memory.ptr, Layout::new::<[u8; 24]>()) };
+/// assert_eq!(alloc.len(), 1);
+///
+/// // The block is reused for another allocation in range
+/// let recycled = alloc.alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)?;
+/// assert_eq!(recycled.ptr, memory.ptr);
+/// assert!(alloc.is_empty());
+/// # unsafe { alloc.dealloc(recycled.ptr, Layout::new::<[u8; 16]>()) };
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct FreeList<A: AllocRef, const MIN: usize, const MAX: usize> {
+    parent: A,
+    root: Option<NonNull<Node>>,
+    len: usize,
+    max_len: Option<usize>,
+}
+
+impl<A: AllocRef, const MIN: usize, const MAX: usize> FreeList<A, MIN, MAX> {
+    const fn assert_bounds() {
+        assert!(MIN <= MAX, "MIN must be smaller than or equal to MAX");
+        assert!(
+            MAX >= mem::size_of::<Node>(),
+            "MAX must be greater than or equal to the size of a pointer"
+        );
+    }
+
+    /// Creates a new `FreeList` with an unlimited list length.
+    pub const fn new(parent: A) -> Self {
+        Self {
+            parent,
+            root: None,
+            len: 0,
+            max_len: None,
+        }
+    }
+
+    /// Creates a new `FreeList`, which holds at most `max_len` blocks.
+    pub const fn with_max_len(parent: A, max_len: usize) -> Self {
+        Self {
+            parent,
+            root: None,
+            len: 0,
+            max_len: Some(max_len),
+        }
+    }
+
+    /// Returns a reference to the parent allocator.
+    pub const fn parent(&self) -> &A {
+        &self.parent
+    }
+
+    /// Returns the number of blocks in the list.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the list does not contain any blocks.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the maximum number of blocks the list can hold, if limited.
+    pub const fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    /// Returns all blocks in the list to the parent allocator.
+    pub fn clear(&mut self) {
+        while let Some(node) = self.pop() {
+            unsafe { self.parent.dealloc(node.cast(), Self::block_layout()) }
+        }
+    }
+
+    fn block_layout() -> Layout {
+        Self::assert_bounds();
+        unsafe { Layout::from_size_align_unchecked(MAX, mem::align_of::<Node>()) }
+    }
+
+    fn is_in_range(layout: Layout) -> bool {
+        layout.size() >= MIN && layout.size() <= MAX && layout.align() <= mem::align_of::<Node>()
+    }
+
+    /// Limits the size of a block returned by the parent for a request smaller than `MIN`.
+    ///
+    /// Otherwise, the block could be deallocated with a size in range and would be put into the
+    /// list, although it's smaller than `MAX` or not aligned for a `Node`.
+    fn forwarded(layout: Layout, memory: MemoryBlock) -> MemoryBlock {
+        if layout.size() < MIN {
+            MemoryBlock {
+                ptr: memory.ptr,
+                size: cmp::min(memory.size, MIN - 1),
+            }
+        } else {
+            memory
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.max_len.map_or(false, |max_len| self.len >= max_len)
+    }
+
+    fn pop(&mut self) -> Option<NonNull<Node>> {
+        let node = self.root?;
+        self.root = unsafe { node.as_ref().next };
+        self.len -= 1;
+        Some(node)
+    }
+
+    unsafe fn push(&mut self, ptr: NonNull<u8>) {
+        let node = ptr.cast::<Node>();
+        node.as_ptr().write(Node { next: self.root });
+        self.root = Some(node);
+        self.len += 1;
+    }
+
+    unsafe fn reallocate(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_layout: Layout,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        if placement == ReallocPlacement::MayMove {
+            let new_memory = self.alloc(new_layout, init)?;
+            let size = cmp::min(layout.size(), new_layout.size());
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), size);
+            self.dealloc(ptr, layout);
+            Ok(new_memory)
+        } else {
+            Err(AllocErr)
+        }
+    }
+}
+
+impl<A: AllocRef, const MIN: usize, const MAX: usize> Drop for FreeList<A, MIN, MAX> {
+    fn drop(&mut self) {
+        self.clear()
+    }
+}
+
+impl<A: AllocRef + fmt::Debug, const MIN: usize, const MAX: usize> fmt::Debug
+    for FreeList<A, MIN, MAX>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FreeList")
+            .field("parent", &self.parent)
+            .field("len", &self.len)
+            .field("max_len", &self.max_len)
+            .finish()
+    }
+}
+
+unsafe impl<A: AllocRef, const MIN: usize, const MAX: usize> AllocRef for FreeList<A, MIN, MAX> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        if !Self::is_in_range(layout) {
+            let memory = self.parent.alloc(layout, init)?;
+            return Ok(Self::forwarded(layout, memory));
+        }
+
+        if let Some(node) = self.pop() {
+            let memory = MemoryBlock {
+                ptr: node.cast(),
+                size: MAX,
+            };
+            unsafe { init.init(memory) };
+            Ok(memory)
+        } else {
+            let memory = self.parent.alloc(Self::block_layout(), init)?;
+            Ok(MemoryBlock {
+                ptr: memory.ptr,
+                size: MAX,
+            })
+        }
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        if !Self::is_in_range(layout) {
+            self.parent.dealloc(ptr, layout)
+        } else if self.is_full() {
+            self.parent.dealloc(ptr, Self::block_layout())
+        } else {
+            self.push(ptr)
+        }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size >= layout.size(),
+            "`new_size` must be greater than or equal to `layout.size()`"
+        );
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+
+        match (Self::is_in_range(layout), Self::is_in_range(new_layout)) {
+            (true, true) => {
+                let memory = MemoryBlock { ptr, size: MAX };
+                init.init_offset(memory, layout.size());
+                Ok(memory)
+            }
+            (false, false) => {
+                let memory = self.parent.grow(ptr, layout, new_size, placement, init)?;
+                Ok(Self::forwarded(new_layout, memory))
+            }
+            _ => self.reallocate(ptr, layout, new_layout, placement, init),
+        }
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size <= layout.size(),
+            "`new_size` must be smaller than or equal to `layout.size()`"
+        );
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+
+        match (Self::is_in_range(layout), Self::is_in_range(new_layout)) {
+            (true, true) => Ok(MemoryBlock { ptr, size: MAX }),
+            (false, false) => {
+                let memory = self.parent.shrink(ptr, layout, new_size, placement)?;
+                Ok(Self::forwarded(new_layout, memory))
+            }
+            _ => self.reallocate(ptr, layout, new_layout, placement, AllocInit::Uninitialized),
+        }
+    }
+}
+
+impl<A: AllocRef + Owns, const MIN: usize, const MAX: usize> Owns for FreeList<A, MIN, MAX> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.parent.owns(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FreeList;
+    use crate::{
+        helper::{self, AsSlice},
+        ChunkAlloc,
+        Owns,
+        Region,
+    };
+    use std::alloc::{AllocInit, AllocRef, Layout, ReallocPlacement, System};
+
+    #[test]
+    fn alloc() {
+        let mut alloc = helper::tracker(FreeList::<_, 16, 32>::new(helper::tracker(System)));
+
+        let memory = alloc
+            .alloc(Layout::new::<[u8; 16]>(), AllocInit::Zeroed)
+            .expect("Could not allocate 16 bytes");
+        assert_eq!(memory.size, 32);
+
+        let small = alloc
+            .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        assert_eq!(small.size, 8);
+
+        unsafe {
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 16]>());
+            alloc.dealloc(small.ptr, Layout::new::<[u8; 8]>());
+        }
+    }
+
+    #[test]
+    fn recycle() {
+        let mut alloc = FreeList::<_, 16, 32>::new(helper::tracker(System));
+
+        let memory = alloc
+            .alloc(Layout::new::<[u8; 32]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 32 bytes");
+        unsafe {
+            memory.ptr.as_ptr().write_bytes(1, memory.size);
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 32]>());
+        }
+        assert_eq!(alloc.len(), 1);
+
+        let recycled = alloc
+            .alloc(Layout::new::<[u8; 20]>(), AllocInit::Zeroed)
+            .expect("Could not allocate 20 bytes");
+        assert_eq!(recycled.ptr, memory.ptr);
+        assert!(alloc.is_empty());
+        unsafe {
+            assert_eq!(recycled.as_slice(), &[0; 32][..]);
+            alloc.dealloc(recycled.ptr, Layout::new::<[u8; 20]>());
+        }
+        assert_eq!(alloc.len(), 1);
+    }
+
+    #[test]
+    fn max_len() {
+        let mut alloc = FreeList::<_, 16, 16>::with_max_len(helper::tracker(System), 1);
+        assert_eq!(alloc.max_len(), Some(1));
+
+        let layout = Layout::new::<[u8; 16]>();
+        let first = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+        let second = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+        unsafe {
+            alloc.dealloc(first.ptr, layout);
+            alloc.dealloc(second.ptr, layout);
+        }
+        assert_eq!(alloc.len(), 1);
+
+        alloc.clear();
+        assert!(alloc.is_empty());
+    }
+
+    #[test]
+    fn grow() {
+        let mut alloc = helper::tracker(FreeList::<_, 16, 32>::new(helper::tracker(System)));
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.ptr.as_ptr().write_bytes(1, 8);
+
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 8]>(),
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(memory.size, 32);
+            assert_eq!(&memory.as_slice()[..8], &[1; 8][..]);
+            assert_eq!(&memory.as_slice()[8..], &[0; 24][..]);
+
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    32,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 32 bytes");
+            assert_eq!(memory.size, 32);
+
+            alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 32]>(),
+                    64,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 64 bytes in place");
+
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 32]>(),
+                    64,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 64 bytes");
+            assert_eq!(&memory.as_slice()[..8], &[1; 8][..]);
+
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 64]>());
+        }
+    }
+
+    #[test]
+    fn shrink() {
+        let mut alloc = helper::tracker(FreeList::<_, 16, 32>::new(helper::tracker(System)));
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 64]>(), AllocInit::Zeroed)
+                .expect("Could not allocate 64 bytes");
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 64]>(),
+                    24,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 24 bytes");
+            assert_eq!(memory.size, 32);
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 24]>(),
+                    16,
+                    ReallocPlacement::InPlace,
+                )
+                .expect("Could not shrink to 16 bytes");
+            assert_eq!(memory.size, 32);
+
+            alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    8,
+                    ReallocPlacement::InPlace,
+                )
+                .expect_err("Could shrink to 8 bytes in place");
+
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 16]>());
+        }
+    }
+
+    #[test]
+    fn owns() {
+        let mut data = [0; 64];
+        let mut alloc = FreeList::<_, 16, 32>::new(Region::new(&mut data));
+
+        let memory = alloc
+            .alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+        assert!(alloc.owns(memory));
+    }
+
+    #[test]
+    fn forward_small() {
+        let mut alloc = FreeList::<_, 16, 32>::new(ChunkAlloc::<_, 64>(helper::tracker(System)));
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            // `ChunkAlloc` returns 64 bytes, but a size in range must not be used for `dealloc`
+            assert_eq!(memory.size, 15);
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 15]>());
+            assert!(alloc.is_empty());
+
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 8]>(),
+                    12,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 12 bytes");
+            assert_eq!(memory.size, 15);
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 12]>());
+            assert!(alloc.is_empty());
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{Owns, Region};
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    cmp,
+    fmt,
+    iter,
+    ptr::{self, NonNull},
+    slice,
+};
+
+struct Chunk {
+    next: Option<NonNull<Chunk>>,
+    layout: Layout,
+    // The lifetime is bound to the chunk, which is deallocated by `GrowingRegion`
+    region: Region<'static>,
+}
+
+/// Allocator over a list of regions, which are allocated from a parent allocator on demand.
+///
+/// When the current region is exhausted, a new region is allocated from the parent and prepended
+/// to the list. Every new region is twice as large as the previous one, but at least large enough
+/// to serve the request. Deallocating, growing, and shrinking is routed to the region which owns
+/// the block. Like [`Region`], only the latest block of a region can actually be deallocated.
+///
+/// All regions are returned to the parent allocator on [`dealloc_all`] or when `GrowingRegion` is
+/// dropped.
+///
+/// [`dealloc_all`]: Self::dealloc_all
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{GrowingRegion, Owns};
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+/// use std::alloc::System;
+///
+/// let mut alloc = GrowingRegion::new(System, 32);
+///
+/// let small = alloc.alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)?;
+/// assert_eq!(alloc.num_regions(), 1);
+///
+/// let big = alloc.alloc(Layout::new::<[u8; 128]>(), AllocInit::Uninitialized)?;
+/// assert_eq!(alloc.num_regions(), 2);
+/// assert!(alloc.owns(small));
+/// assert!(alloc.owns(big));
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct GrowingRegion<A: AllocRef> {
+    parent: A,
+    root: Option<NonNull<Chunk>>,
+    next_capacity: usize,
+}
+
+impl<A: AllocRef> GrowingRegion<A> {
+    /// Creates a new `GrowingRegion`. The first region will have a capacity of at least
+    /// `initial_capacity` bytes.
+    ///
+    /// No memory is allocated until the first allocation is requested.
+    pub const fn new(parent: A, initial_capacity: usize) -> Self {
+        Self {
+            parent,
+            root: None,
+            next_capacity: initial_capacity,
+        }
+    }
+
+    /// Returns a reference to the parent allocator.
+    pub const fn parent(&self) -> &A {
+        &self.parent
+    }
+
+    /// Returns the number of regions allocated from the parent.
+    pub fn num_regions(&self) -> usize {
+        self.chunks().count()
+    }
+
+    /// Returns the total capacity of all regions.
+    pub fn capacity(&self) -> usize {
+        self.chunks()
+            .map(|chunk| unsafe { chunk.as_ref().region.capacity() })
+            .sum()
+    }
+
+    /// Resets the allocator.
+    ///
+    /// All regions except the current one, which is the largest, are returned to the parent. The
+    /// current region is reset, so its capacity can be reused.
+    pub fn reset(&mut self) {
+        if let Some(mut root) = self.root {
+            unsafe {
+                let root = root.as_mut();
+                self.dealloc_chunks(root.next.take());
+                root.region.reset();
+            }
+        }
+    }
+
+    /// Returns all regions to the parent allocator.
+    pub fn dealloc_all(&mut self) {
+        let root = self.root.take();
+        unsafe { self.dealloc_chunks(root) }
+    }
+
+    fn chunks(&self) -> impl Iterator<Item = NonNull<Chunk>> + '_ {
+        iter::successors(self.root, |chunk| unsafe { chunk.as_ref().next })
+    }
+
+    fn owning_region(&mut self, memory: MemoryBlock) -> Option<&mut Region<'static>> {
+        self.chunks()
+            .find(|chunk| unsafe { chunk.as_ref().region.owns(memory) })
+            .map(|mut chunk| unsafe { &mut chunk.as_mut().region })
+    }
+
+    unsafe fn dealloc_chunks(&mut self, mut chunk: Option<NonNull<Chunk>>) {
+        while let Some(current) = chunk {
+            let Chunk { next, layout, .. } = current.as_ptr().read();
+            self.parent.dealloc(current.cast(), layout);
+            chunk = next;
+        }
+    }
+
+    fn push_chunk(&mut self, layout: Layout) -> Result<&mut Region<'static>, AllocErr> {
+        let padded_size = layout
+            .size()
+            .checked_add(layout.align() - 1)
+            .ok_or(AllocErr)?;
+        let capacity = cmp::max(self.next_capacity, padded_size);
+        let (chunk_layout, offset) = Layout::new::<Chunk>()
+            .extend(Layout::from_size_align(capacity, 1).map_err(|_| AllocErr)?)
+            .map_err(|_| AllocErr)?;
+
+        let memory = self.parent.alloc(chunk_layout, AllocInit::Zeroed)?;
+        self.next_capacity = capacity.saturating_mul(2);
+
+        unsafe {
+            let data =
+                slice::from_raw_parts_mut(memory.ptr.as_ptr().add(offset), memory.size - offset);
+            let chunk = memory.ptr.cast::<Chunk>();
+            chunk.as_ptr().write(Chunk {
+                next: self.root,
+                layout: chunk_layout,
+                region: Region::new(data),
+            });
+            self.root = Some(chunk);
+            Ok(&mut (*chunk.as_ptr()).region)
+        }
+    }
+}
+
+impl<A: AllocRef> Drop for GrowingRegion<A> {
+    fn drop(&mut self) {
+        self.dealloc_all()
+    }
+}
+
+impl<A: AllocRef> fmt::Debug for GrowingRegion<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrowingRegion")
+            .field("num_regions", &self.num_regions())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+unsafe impl<A: AllocRef> AllocRef for GrowingRegion<A> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        if let Some(mut root) = self.root {
+            if let Ok(memory) = unsafe { root.as_mut().region.alloc(layout, init) } {
+                return Ok(memory);
+            }
+        }
+        self.push_chunk(layout)?.alloc(layout, init)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        let memory = MemoryBlock {
+            ptr,
+            size: layout.size(),
+        };
+        if let Some(region) = self.owning_region(memory) {
+            region.dealloc(ptr, layout)
+        } else {
+            debug_assert!(
+                false,
+                "`ptr` must denote a block of memory currently allocated via this allocator"
+            )
+        }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let memory = MemoryBlock {
+            ptr,
+            size: layout.size(),
+        };
+        let region = self.owning_region(memory).ok_or(AllocErr)?;
+        if let Ok(memory) = region.grow(ptr, layout, new_size, ReallocPlacement::InPlace, init) {
+            Ok(memory)
+        } else if placement == ReallocPlacement::MayMove {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_memory = self.alloc(new_layout, init)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), layout.size());
+            self.dealloc(ptr, layout);
+            Ok(new_memory)
+        } else {
+            Err(AllocErr)
+        }
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let memory = MemoryBlock {
+            ptr,
+            size: layout.size(),
+        };
+        self.owning_region(memory)
+            .ok_or(AllocErr)?
+            .shrink(ptr, layout, new_size, placement)
+    }
+}
+
+impl<A: AllocRef> Owns for GrowingRegion<A> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.chunks()
+            .any(|chunk| unsafe { chunk.as_ref().region.owns(memory) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::helper::{self, AsSlice};
+    use std::alloc::System;
+
+    #[test]
+    fn alloc() {
+        let mut alloc = GrowingRegion::new(helper::tracker(System), 32);
+        assert_eq!(alloc.num_regions(), 0);
+
+        let first = alloc
+            .alloc(Layout::new::<[u8; 24]>(), AllocInit::Zeroed)
+            .expect("Could not allocate 24 bytes");
+        assert_eq!(alloc.num_regions(), 1);
+        assert_eq!(alloc.capacity(), 32);
+        unsafe { assert_eq!(first.as_slice(), &[0; 24][..]) };
+
+        let second = alloc
+            .alloc(Layout::new::<[u8; 24]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 24 bytes");
+        assert_eq!(alloc.num_regions(), 2);
+        assert_eq!(alloc.capacity(), 32 + 64);
+
+        let third = alloc
+            .alloc(Layout::new::<[u8; 256]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 256 bytes");
+        assert_eq!(alloc.num_regions(), 3);
+
+        assert!(alloc.owns(first));
+        assert!(alloc.owns(second));
+        assert!(alloc.owns(third));
+    }
+
+    #[test]
+    fn dealloc() {
+        let mut alloc = GrowingRegion::new(helper::tracker(System), 32);
+        let layout = Layout::new::<[u8; 24]>();
+
+        let first = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 24 bytes");
+        let second = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 24 bytes");
+
+        unsafe {
+            alloc.dealloc(first.ptr, layout);
+            alloc.dealloc(second.ptr, layout);
+        }
+        assert!(!alloc.owns(first));
+        assert!(!alloc.owns(second));
+        assert_eq!(alloc.num_regions(), 2);
+
+        alloc.dealloc_all();
+        assert_eq!(alloc.num_regions(), 0);
+    }
+
+    #[test]
+    fn grow() {
+        let mut alloc = GrowingRegion::new(helper::tracker(System), 32);
+        let layout = Layout::new::<[u8; 8]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.as_slice_mut().copy_from_slice(&[1; 8]);
+
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    32,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 32 bytes");
+            assert_eq!(alloc.num_regions(), 1);
+
+            alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 32]>(),
+                    64,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 64 bytes in place");
+
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 32]>(),
+                    64,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 64 bytes");
+            assert_eq!(alloc.num_regions(), 2);
+            assert_eq!(&memory.as_slice()[..8], &[1; 8][..]);
+            assert_eq!(&memory.as_slice()[8..], &[0; 56][..]);
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 64]>(),
+                    16,
+                    ReallocPlacement::InPlace,
+                )
+                .expect("Could not shrink to 16 bytes");
+            assert_eq!(memory.size, 16);
+        }
+    }
+
+    #[test]
+    fn reset() {
+        let mut alloc = GrowingRegion::new(helper::tracker(System), 16);
+
+        for _ in 0..4 {
+            alloc
+                .alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 16 bytes");
+        }
+        assert_eq!(alloc.num_regions(), 3);
+
+        alloc.reset();
+        assert_eq!(alloc.num_regions(), 1);
+        assert_eq!(alloc.capacity(), 64);
+
+        for _ in 0..4 {
+            alloc
+                .alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 16 bytes");
+        }
+        assert_eq!(alloc.num_regions(), 1);
+    }
+}
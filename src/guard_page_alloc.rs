@@ -0,0 +1,428 @@
+use crate::{Owns, PageAlloc};
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    cmp,
+    mem,
+    ptr::{self, NonNull},
+};
+
+/// Where the guard page is placed relative to a block of [`GuardPageAlloc`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GuardPagePosition {
+    /// The guard page is placed in front of the block to detect buffer underflows.
+    Before,
+    /// The guard page is placed behind the block to detect buffer overflows.
+    After,
+}
+
+/// Bookkeeping data stored in every mapping.
+///
+/// The headers of all mappings form a doubly linked list, so `owns` can be answered without
+/// touching foreign memory.
+struct Header {
+    prev: Option<NonNull<Header>>,
+    next: Option<NonNull<Header>>,
+    base: NonNull<u8>,
+    len: usize,
+}
+
+/// Maps every allocation with `mmap` next to an inaccessible guard page.
+///
+/// Each block is placed directly in front of (or behind, depending on [`GuardPagePosition`]) a
+/// page mapped with `PROT_NONE`. Accessing memory beyond the block triggers a segmentation fault
+/// instead of silently corrupting the heap. When a block is deallocated, its pages are unmapped,
+/// so any later access faults as well.
+///
+/// As every block requires at least two pages, this allocator is only suited for debugging.
+/// Alignments greater than the page size are not supported.
+///
+/// Placing the block directly at the guard page is only possible up to the alignment of the
+/// block. An overflow or underflow by less than the alignment may not be detected.
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{GuardPageAlloc, GuardPagePosition, Owns};
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+///
+/// let mut alloc = GuardPageAlloc::new(GuardPagePosition::After);
+///
+/// let memory = alloc.alloc(Layout::new::<[u8; 8]>(), AllocInit::Zeroed)?;
+/// assert!(alloc.owns(memory));
+///
+/// // Writing at `memory.ptr.as_ptr().add(8)` would trigger a segmentation fault
+///
+/// unsafe { alloc.dealloc(memory.ptr, Layout::new::<[u8; 8]>()) };
+/// assert!(!alloc.owns(memory));
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+#[derive(Debug)]
+pub struct GuardPageAlloc {
+    position: GuardPagePosition,
+    head: Option<NonNull<Header>>,
+}
+
+/// Returns the number of bytes of accessible memory needed for a block fitting `layout`.
+fn data_len(layout: Layout, page_size: usize) -> Option<usize> {
+    let len = layout
+        .size()
+        .checked_add(mem::size_of::<Header>())?
+        .checked_add(layout.align() - 1)?;
+    let len = len.checked_add(page_size - 1)? & !(page_size - 1);
+    Some(len)
+}
+
+impl GuardPageAlloc {
+    pub const fn new(position: GuardPagePosition) -> Self {
+        Self {
+            position,
+            head: None,
+        }
+    }
+
+    /// Returns where the guard pages are placed.
+    pub const fn position(&self) -> GuardPagePosition {
+        self.position
+    }
+
+    /// Returns the location of the header for a mapping at `base` with `data_len` bytes of
+    /// accessible memory.
+    fn header(&self, base: NonNull<u8>, data_len: usize, page_size: usize) -> NonNull<Header> {
+        let offset = match self.position {
+            GuardPagePosition::Before => page_size + data_len - mem::size_of::<Header>(),
+            GuardPagePosition::After => 0,
+        };
+        unsafe { NonNull::new_unchecked(base.as_ptr().add(offset)).cast() }
+    }
+
+    /// Returns the base of the mapping of the block at `ptr`.
+    unsafe fn base(&self, ptr: NonNull<u8>, layout: Layout, page_size: usize) -> NonNull<u8> {
+        let base = match self.position {
+            GuardPagePosition::Before => ptr.as_ptr() as usize - page_size,
+            GuardPagePosition::After => {
+                let end =
+                    (ptr.as_ptr() as usize + layout.size() + page_size - 1) & !(page_size - 1);
+                end - data_len(layout, page_size).expect("Invalid layout")
+            }
+        };
+        NonNull::new_unchecked(base as *mut u8)
+    }
+
+    unsafe fn link(&mut self, mut header: NonNull<Header>) {
+        header.as_mut().next = self.head;
+        if let Some(mut head) = self.head {
+            head.as_mut().prev = Some(header);
+        }
+        self.head = Some(header);
+    }
+
+    unsafe fn unlink(&mut self, header: NonNull<Header>) {
+        let Header { prev, next, .. } = *header.as_ptr();
+        match prev {
+            Some(mut prev) => prev.as_mut().next = next,
+            None => self.head = next,
+        }
+        if let Some(mut next) = next {
+            next.as_mut().prev = prev;
+        }
+    }
+}
+
+unsafe impl AllocRef for GuardPageAlloc {
+    fn alloc(&mut self, layout: Layout, _init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        let page_size = PageAlloc::page_size();
+        if layout.align() > page_size {
+            return Err(AllocErr);
+        }
+        let data_len = data_len(layout, page_size).ok_or(AllocErr)?;
+        let len = data_len.checked_add(page_size).ok_or(AllocErr)?;
+
+        unsafe {
+            let base = libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+            if base == libc::MAP_FAILED {
+                return Err(AllocErr);
+            }
+            let base = NonNull::new_unchecked(base.cast::<u8>());
+
+            let (guard, ptr) = match self.position {
+                GuardPagePosition::Before => (base.as_ptr(), base.as_ptr().add(page_size)),
+                GuardPagePosition::After => {
+                    let guard = base.as_ptr().add(data_len);
+                    let ptr = (guard as usize - layout.size()) & !(layout.align() - 1);
+                    (guard, ptr as *mut u8)
+                }
+            };
+            if libc::mprotect(guard.cast(), page_size, libc::PROT_NONE) != 0 {
+                libc::munmap(base.as_ptr().cast(), len);
+                return Err(AllocErr);
+            }
+
+            let header = self.header(base, data_len, page_size);
+            header.as_ptr().write(Header {
+                prev: None,
+                next: None,
+                base,
+                len,
+            });
+            self.link(header);
+
+            // Anonymous mappings are zero-initialized, so `init` can be ignored
+            Ok(MemoryBlock {
+                ptr: NonNull::new_unchecked(ptr),
+                size: layout.size(),
+            })
+        }
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        let page_size = PageAlloc::page_size();
+        let base = self.base(ptr, layout, page_size);
+        let data_len = data_len(layout, page_size).expect("Invalid layout");
+        let header = self.header(base, data_len, page_size);
+        debug_assert_eq!(
+            header.as_ref().base,
+            base,
+            "`ptr` must denote a block of memory currently allocated via this allocator"
+        );
+
+        self.unlink(header);
+        let len = header.as_ref().len;
+        libc::munmap(base.as_ptr().cast(), len);
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size >= layout.size(),
+            "`new_size` must be greater than or equal to `layout.size()`"
+        );
+        self.move_block(ptr, layout, new_size, placement, init)
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size <= layout.size(),
+            "`new_size` must be smaller than or equal to `layout.size()`"
+        );
+        self.move_block(ptr, layout, new_size, placement, AllocInit::Uninitialized)
+    }
+}
+
+impl GuardPageAlloc {
+    /// Moves the block at `ptr` into a new mapping, so it stays adjacent to the guard page.
+    unsafe fn move_block(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        if new_size == layout.size() {
+            return Ok(MemoryBlock {
+                ptr,
+                size: new_size,
+            });
+        }
+        if placement == ReallocPlacement::InPlace {
+            return Err(AllocErr);
+        }
+
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_memory = self.alloc(new_layout, init)?;
+        ptr::copy_nonoverlapping(
+            ptr.as_ptr(),
+            new_memory.ptr.as_ptr(),
+            cmp::min(layout.size(), new_size),
+        );
+        self.dealloc(ptr, layout);
+        Ok(new_memory)
+    }
+}
+
+impl Owns for GuardPageAlloc {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        let start = memory.ptr.as_ptr() as usize;
+        let mut current = self.head;
+        while let Some(header) = current {
+            let header = unsafe { header.as_ref() };
+            let base = header.base.as_ptr() as usize;
+            if base <= start && start + memory.size <= base + header.len {
+                return true;
+            }
+            current = header.next;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::helper::{self, AsSlice};
+
+    fn alloc_dealloc(position: GuardPagePosition) {
+        let mut alloc = helper::tracker(GuardPageAlloc::new(position));
+
+        for &(size, align) in &[(0, 1), (1, 1), (8, 8), (13, 4), (4096, 16), (5000, 64)] {
+            let layout = Layout::from_size_align(size, align).expect("Invalid layout");
+            let memory = alloc
+                .alloc(layout, AllocInit::Zeroed)
+                .unwrap_or_else(|_| panic!("Could not allocate {} bytes", size));
+            assert_eq!(memory.ptr.as_ptr() as usize % align, 0);
+            unsafe {
+                assert_eq!(memory.as_slice(), &vec![0; size][..]);
+                memory.as_slice_mut().iter_mut().for_each(|byte| *byte = 1);
+                alloc.dealloc(memory.ptr, layout);
+            }
+        }
+    }
+
+    #[test]
+    fn alloc_before() {
+        alloc_dealloc(GuardPagePosition::Before)
+    }
+
+    #[test]
+    fn alloc_after() {
+        alloc_dealloc(GuardPagePosition::After)
+    }
+
+    #[test]
+    fn alloc_align_too_large() {
+        let mut alloc = GuardPageAlloc::new(GuardPagePosition::After);
+        alloc
+            .alloc(
+                Layout::from_size_align(8, PageAlloc::page_size() * 2).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect_err("Could allocate with an alignment greater than the page size");
+    }
+
+    #[test]
+    fn realloc() {
+        let mut alloc = helper::tracker(GuardPageAlloc::new(GuardPagePosition::After));
+        let layout = Layout::new::<[u8; 8]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.as_slice_mut().copy_from_slice(&[1; 8]);
+
+            alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Zeroed,
+                )
+                .expect_err("Could grow to 16 bytes in place");
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(&memory.as_slice()[..8], &[1; 8][..]);
+            assert_eq!(&memory.as_slice()[8..], &[0; 8][..]);
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    4,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 4 bytes");
+            assert_eq!(memory.as_slice(), &[1; 4][..]);
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 4]>());
+        }
+    }
+
+    #[test]
+    fn owns() {
+        let mut alloc = GuardPageAlloc::new(GuardPagePosition::Before);
+        let layout = Layout::new::<[u8; 8]>();
+
+        let first = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let second = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        assert!(alloc.owns(first));
+        assert!(alloc.owns(second));
+
+        unsafe { alloc.dealloc(first.ptr, layout) };
+        assert!(!alloc.owns(first));
+        assert!(alloc.owns(second));
+
+        unsafe { alloc.dealloc(second.ptr, layout) };
+        assert!(!alloc.owns(second));
+    }
+
+    /// Writes one byte beyond the block in a child process and returns if it was killed by
+    /// `SIGSEGV`.
+    fn access_faults(position: GuardPagePosition, offset: isize) -> bool {
+        let mut alloc = GuardPageAlloc::new(position);
+        let layout = Layout::new::<[u8; 8]>();
+        let memory = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+
+        unsafe {
+            match libc::fork() {
+                -1 => panic!("Could not fork the process"),
+                0 => {
+                    memory.ptr.as_ptr().offset(offset).write_volatile(0);
+                    libc::_exit(0)
+                }
+                pid => {
+                    let mut status = 0;
+                    libc::waitpid(pid, &mut status, 0);
+                    alloc.dealloc(memory.ptr, layout);
+                    libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == libc::SIGSEGV
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn overflow() {
+        assert!(!access_faults(GuardPagePosition::After, 7));
+        assert!(access_faults(GuardPagePosition::After, 8));
+    }
+
+    #[test]
+    fn underflow() {
+        assert!(!access_faults(GuardPagePosition::Before, 0));
+        assert!(access_faults(GuardPagePosition::Before, -1));
+    }
+}
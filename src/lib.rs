@@ -13,20 +13,35 @@
 )]
 #![allow(incomplete_features, clippy::must_use_candidate)]
 
-#[cfg(any(feature = "alloc", doc))]
+#[cfg(any(feature = "alloc", doc, test))]
 extern crate alloc;
 
 pub mod stats;
 
 mod affix;
+mod bitmapped_block;
+mod bucketizer;
 mod callback_ref;
+mod canary;
 mod chunk_alloc;
 mod fallback_alloc;
+mod free_list;
+#[cfg(all(feature = "libc", target_os = "linux"))]
+mod guard_page_alloc;
+mod growing_region;
 mod memory_marker;
 mod null_alloc;
+mod owned_region;
+#[cfg(all(feature = "libc", unix))]
+mod page_alloc;
 mod proxy;
+mod quarantine;
 mod region;
+mod reverse_region;
 mod segregate_alloc;
+mod shared_region;
+mod size_header;
+mod stack_alloc;
 
 use core::{
     alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
@@ -35,16 +50,35 @@ use core::{
 
 pub use self::{
     affix::Affix,
+    bitmapped_block::BitmappedBlock,
+    bucketizer::Bucketizer,
     callback_ref::CallbackRef,
+    canary::{Canary, CanaryHandler, CanarySide},
     chunk_alloc::ChunkAlloc,
     fallback_alloc::FallbackAlloc,
-    memory_marker::MemoryMarker,
+    free_list::FreeList,
+    growing_region::GrowingRegion,
+    memory_marker::{MemoryMarker, PatternMarker},
     null_alloc::NullAlloc,
+    owned_region::OwnedRegion,
     proxy::Proxy,
-    region::Region,
+    quarantine::Quarantine,
+    region::{Region, RegionCheckpoint},
+    reverse_region::ReverseRegion,
     segregate_alloc::SegregateAlloc,
+    shared_region::SharedRegion,
+    size_header::SizeHeader,
+    stack_alloc::StackAlloc,
 };
 
+#[cfg(all(feature = "libc", target_os = "linux"))]
+#[cfg_attr(doc, doc(cfg(all(feature = "libc", target_os = "linux"))))]
+pub use self::guard_page_alloc::{GuardPageAlloc, GuardPagePosition};
+
+#[cfg(all(feature = "libc", unix))]
+#[cfg_attr(doc, doc(cfg(all(feature = "libc", unix))))]
+pub use self::page_alloc::PageAlloc;
+
 type Result<T = MemoryBlock, E = AllocErr> = core::result::Result<T, E>;
 
 /// Trait to determine if a given `MemoryBlock` is owned by an allocator.
@@ -94,168 +128,23 @@ unsafe fn shrink<A1: AllocRef, A2: AllocRef>(
 
 #[cfg(test)]
 pub(crate) mod helper {
-    use crate::{CallbackRef, Proxy, Result};
+    use crate::{
+        stats::{LeakTracker, Validator},
+        Proxy,
+    };
     use std::{
-        alloc::{AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
-        collections::HashMap,
-        ptr::NonNull,
+        alloc::{AllocRef, MemoryBlock},
         slice,
-        sync::{Mutex, PoisonError},
     };
 
-    #[derive(Default)]
-    pub struct Tracker {
-        map: Mutex<HashMap<NonNull<u8>, (usize, Layout)>>,
-    }
-
-    impl Tracker {
-        fn insert(&self, memory: MemoryBlock, layout: Layout) {
-            self.map
-                .lock()
-                .unwrap_or_else(PoisonError::into_inner)
-                .insert(memory.ptr, (memory.size, layout));
-        }
-
-        #[track_caller]
-        fn remove(&self, ptr: NonNull<u8>, layout: Layout) {
-            let (size, old_layout) = self
-                .map
-                .lock()
-                .unwrap_or_else(PoisonError::into_inner)
-                .remove(&ptr)
-                .expect(
-                    "dealloc: `ptr` must denote a block of memory currently allocated via this \
-                     allocator",
-                );
-            assert_eq!(
-                layout.align(),
-                old_layout.align(),
-                "dealloc: `layout` must fit that block of memory. Expected alignment of {}, got {}",
-                old_layout.align(),
-                layout.align()
-            );
-            if layout.size() < old_layout.size() || layout.size() > size {
-                if size == old_layout.size() {
-                    panic!(
-                        "dealloc: `layout` must fit that block of memory. Expected size of {}, \
-                         got {}",
-                        old_layout.size(),
-                        layout.size()
-                    )
-                } else {
-                    panic!(
-                        "dealloc: `layout` must fit that block of memory. Expected size between \
-                         {}..={}, got {}",
-                        old_layout.size(),
-                        size,
-                        layout.size()
-                    )
-                }
-            }
-        }
-    }
-
-    unsafe impl CallbackRef for Tracker {
-        fn alloc(&self, layout: Layout, _init: AllocInit, result: Result) {
-            if let Ok(memory) = result {
-                self.insert(memory, layout)
-            }
-        }
-
-        #[track_caller]
-        fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
-            self.remove(ptr, layout);
-        }
-
-        #[track_caller]
-        fn grow(
-            &self,
-            ptr: NonNull<u8>,
-            layout: Layout,
-            new_size: usize,
-            _placement: ReallocPlacement,
-            _init: AllocInit,
-            result: Result,
-        ) {
-            assert!(
-                new_size >= layout.size(),
-                "`new_size` must be greater than or equal to `layout.size()`, expected {} >= {}",
-                new_size,
-                layout.size()
-            );
-            let new_layout =
-                Layout::from_size_align(new_size, layout.align()).unwrap_or_else(|_| {
-                    panic!(
-                        "`new_size`, when rounded up to the nearest multiple of `layout.align()`, \
-                         must not overflow (i.e., the rounded value must be less than or equal to \
-                         `usize::MAX`), expected {} to be rounded up to the nearest multiple of {}",
-                        new_size,
-                        layout.align()
-                    )
-                });
-            if let Ok(memory) = result {
-                self.remove(ptr, layout);
-                self.insert(memory, new_layout);
-            }
-        }
-
-        #[track_caller]
-        fn shrink(
-            &self,
-            ptr: NonNull<u8>,
-            layout: Layout,
-            new_size: usize,
-            _placement: ReallocPlacement,
-            result: Result,
-        ) {
-            assert!(
-                new_size <= layout.size(),
-                "`new_size` must be smaller than or equal to `layout.size()`, expected {} <= {}",
-                new_size,
-                layout.size()
-            );
-            let new_layout = Layout::from_size_align(new_size, layout.align()).unwrap();
-            if let Ok(memory) = result {
-                self.remove(ptr, layout);
-                self.insert(memory, new_layout);
-            }
-        }
-
-        fn owns(&self, _success: bool) {}
-    }
-
-    impl Drop for Tracker {
-        fn drop(&mut self) {
-            let map = self.map.get_mut().unwrap_or_else(PoisonError::into_inner);
-            if !map.is_empty() {
-                let mut error = String::from("Not all allocations has been freed:");
-                for (ptr, (size, layout)) in map {
-                    if *size == layout.size() {
-                        error.push_str(&format!(
-                            "\n- {:?}: Layout {{ size: {}, align: {} }}",
-                            ptr,
-                            size,
-                            layout.align()
-                        ));
-                    } else {
-                        error.push_str(&format!(
-                            "\n- {:?}: Layout {{ size: {}..={}, align: {} }}",
-                            ptr,
-                            layout.size(),
-                            size,
-                            layout.align()
-                        ));
-                    }
-                }
-                panic!("{}", error);
-            }
-        }
-    }
-
+    /// Wraps `alloc`, so it panics on invalid arguments and when blocks are leaked.
     pub fn tracker<A: AllocRef>(alloc: A) -> impl AllocRef {
         Proxy {
-            alloc,
-            callbacks: Tracker::default(),
+            alloc: Proxy {
+                alloc,
+                callbacks: LeakTracker::new(),
+            },
+            callbacks: Validator::new(),
         }
     }
 
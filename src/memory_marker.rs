@@ -1,4 +1,8 @@
 use crate::Owns;
+#[cfg(any(doc, feature = "alloc"))]
+use alloc::collections::BTreeMap;
+#[cfg(any(doc, feature = "alloc"))]
+use core::slice;
 use core::{
     alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
     ptr::NonNull,
@@ -12,6 +16,7 @@ use core::{
 /// Studio Debug Heap implementation.
 ///
 /// Once, `const_generics` allows default implementations, the values may be alterd with a parameter.
+/// Until then, [`PatternMarker`] can be used for other patterns.
 ///
 /// [Magic Debug Values]: https://en.wikipedia.org/wiki/Magic_number_%28programming%29#Magic_debug_values
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
@@ -54,10 +59,13 @@ unsafe impl<A: AllocRef> AllocRef for MemoryMarker<A> {
         new_size: usize,
         placement: ReallocPlacement,
     ) -> Result<MemoryBlock, AllocErr> {
-        ptr.as_ptr()
-            .add(new_size)
-            .write_bytes(0xDD, layout.size() - new_size);
-        self.0.shrink(ptr, layout, new_size, placement)
+        let memory = self.0.shrink(ptr, layout, new_size, placement)?;
+        if memory.ptr == ptr && memory.size < layout.size() {
+            ptr.as_ptr()
+                .add(memory.size)
+                .write_bytes(0xDD, layout.size() - memory.size);
+        }
+        Ok(memory)
     }
 }
 
@@ -67,14 +75,317 @@ impl<A: Owns> Owns for MemoryMarker<A> {
     }
 }
 
+/// Marks newly allocated and deallocated memory with the byte patterns `ALLOC` and `DEALLOC`.
+///
+/// Behaves like [`MemoryMarker`], which uses `0xCD` and `0xDD`. [Magic Debug Values] lists
+/// other common values.
+///
+/// In verifying mode, the marker keeps a record of the memory it has freed. When the parent
+/// allocator hands out such memory again, e.g. a [`FreeList`] or a [`Region`] after `reset`, it
+/// checks that the freed bytes still hold `DEALLOC`. This detects writes to freed memory and
+/// panics with the offset of the first corrupted byte. Memory, which was not freed through the
+/// marker, is never checked.
+///
+/// Parents may store bookkeeping data inside of freed blocks, like the link of a [`FreeList`].
+/// The first `reserved` bytes of every freed range are therefore not checked. Parents writing
+/// anywhere else into freed memory, or handing it out to other users in between, may be reported
+/// as corruption. Verifying is therefore meaningless on top of allocators, which store metadata
+/// at unknown offsets in freed memory or share it with other users, like `System`.
+///
+/// Every freed range is recorded until the parent hands it out again. Adjacent ranges are merged,
+/// but memory, which the parent never reuses, stays in the record, so its size grows with the
+/// number of disjoint freed ranges.
+///
+/// [Magic Debug Values]: https://en.wikipedia.org/wiki/Magic_number_%28programming%29#Magic_debug_values
+/// [`Region`]: crate::Region
+/// [`FreeList`]: crate::FreeList
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PatternMarker<A, const ALLOC: u8, const DEALLOC: u8> {
+    pub alloc: A,
+    #[cfg(any(doc, feature = "alloc"))]
+    freed: Option<FreedMemory>,
+}
+
+impl<A, const ALLOC: u8, const DEALLOC: u8> PatternMarker<A, ALLOC, DEALLOC> {
+    /// Creates a new `PatternMarker`, which marks the memory without verifying it.
+    pub const fn new(alloc: A) -> Self {
+        Self {
+            alloc,
+            #[cfg(any(doc, feature = "alloc"))]
+            freed: None,
+        }
+    }
+
+    /// Creates a new `PatternMarker`, which verifies, that memory freed by the marker still holds
+    /// `DEALLOC` when it's allocated again. The first `reserved` bytes of every freed range are
+    /// left to the parent allocator and are not verified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(allocator_api)]
+    ///
+    /// use alloc_compose::{FreeList, PatternMarker};
+    /// use core::mem;
+    /// use std::alloc::{AllocInit, AllocRef, Layout, System};
+    /// use std::panic::{self, AssertUnwindSafe};
+    ///
+    /// let free_list = FreeList::<_, 8, 16>::new(System);
+    /// let mut alloc =
+    ///     PatternMarker::<_, 0xCD, 0xDD>::verifying(free_list, mem::size_of::<usize>());
+    ///
+    /// let memory = alloc.alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)?;
+    /// unsafe { alloc.dealloc(memory.ptr, Layout::new::<[u8; 16]>()) };
+    ///
+    /// // Writing to freed memory is detected, when the block is handed out again
+    /// unsafe { memory.ptr.as_ptr().add(12).write(0) };
+    /// let result = panic::catch_unwind(AssertUnwindSafe(|| {
+    ///     alloc.alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)
+    /// }));
+    /// assert!(result.is_err());
+    /// # Ok::<(), core::alloc::AllocErr>(())
+    /// ```
+    #[cfg(any(doc, feature = "alloc"))]
+    #[cfg_attr(doc, doc(cfg(feature = "alloc")))]
+    pub fn verifying(alloc: A, reserved: usize) -> Self {
+        Self {
+            alloc,
+            freed: Some(FreedMemory {
+                reserved,
+                ranges: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Returns if freed memory is verified when it's allocated again.
+    #[cfg(any(doc, feature = "alloc"))]
+    #[cfg_attr(doc, doc(cfg(feature = "alloc")))]
+    pub fn is_verifying(&self) -> bool {
+        self.freed.is_some()
+    }
+
+    #[cfg(any(doc, feature = "alloc"))]
+    fn verifies(&self) -> bool {
+        self.freed.is_some()
+    }
+
+    #[cfg(not(any(doc, feature = "alloc")))]
+    fn verifies(&self) -> bool {
+        false
+    }
+
+    /// Verifies the memory in `memory` starting at `offset` and forgets all freed memory inside of
+    /// `memory`.
+    #[track_caller]
+    #[allow(unused_variables)]
+    unsafe fn reuse(&mut self, memory: MemoryBlock, offset: usize) {
+        #[cfg(any(doc, feature = "alloc"))]
+        {
+            if let Some(freed) = &mut self.freed {
+                freed.reuse::<DEALLOC>(memory, offset);
+            }
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn record_freed(&mut self, ptr: NonNull<u8>, size: usize) {
+        #[cfg(any(doc, feature = "alloc"))]
+        {
+            if let Some(freed) = &mut self.freed {
+                freed.insert(ptr.as_ptr() as usize, size);
+            }
+        }
+    }
+}
+
+/// Ranges of memory freed by a verifying `PatternMarker`, mapping start to end addresses.
+///
+/// The `reserved` bytes are excluded from the ranges, so every recorded byte has to hold
+/// `DEALLOC`.
+#[cfg(any(doc, feature = "alloc"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FreedMemory {
+    reserved: usize,
+    ranges: BTreeMap<usize, usize>,
+}
+
+#[cfg(any(doc, feature = "alloc"))]
+impl FreedMemory {
+    fn insert(&mut self, start: usize, size: usize) {
+        let end = start + size;
+        self.remove(start, end);
+
+        let mut start = start + self.reserved;
+        let mut end = end;
+        if start >= end {
+            return;
+        }
+        if let Some((&previous_start, &previous_end)) = self.ranges.range(..start).next_back() {
+            if previous_end == start {
+                self.ranges.remove(&previous_start);
+                start = previous_start;
+            }
+        }
+        if let Some(next_end) = self.ranges.remove(&end) {
+            end = next_end;
+        }
+        self.ranges.insert(start, end);
+    }
+
+    /// Removes all ranges overlapping `start..end` and returns the overlapped parts.
+    fn remove(&mut self, start: usize, end: usize) -> alloc::vec::Vec<(usize, usize)> {
+        let overlapping: alloc::vec::Vec<_> = self
+            .ranges
+            .range(..end)
+            .rev()
+            .take_while(|&(_, &range_end)| range_end > start)
+            .map(|(&range_start, &range_end)| (range_start, range_end))
+            .collect();
+
+        for &(range_start, range_end) in &overlapping {
+            self.ranges.remove(&range_start);
+            if range_start < start {
+                self.ranges.insert(range_start, start);
+            }
+            if range_end > end {
+                self.ranges.insert(end, range_end);
+            }
+        }
+        overlapping
+    }
+
+    #[track_caller]
+    unsafe fn reuse<const DEALLOC: u8>(&mut self, memory: MemoryBlock, offset: usize) {
+        let block_start = memory.ptr.as_ptr() as usize;
+        for (range_start, range_end) in self.remove(block_start, block_start + memory.size) {
+            let start = range_start.max(block_start + offset);
+            let end = range_end.min(block_start + memory.size);
+            if start >= end {
+                continue;
+            }
+            let freed = slice::from_raw_parts(start as *const u8, end - start);
+            if let Some(position) = freed.iter().position(|&byte| byte != DEALLOC) {
+                panic!(
+                    "Freed memory at {:?} was written to at offset {}: expected {:#04X}, found \
+                     {:#04X}",
+                    memory.ptr,
+                    start - block_start + position,
+                    DEALLOC,
+                    freed[position]
+                );
+            }
+        }
+    }
+}
+
+unsafe impl<A: AllocRef, const ALLOC: u8, const DEALLOC: u8> AllocRef
+    for PatternMarker<A, ALLOC, DEALLOC>
+{
+    #[track_caller]
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        if !self.verifies() {
+            let memory = self.alloc.alloc(layout, init)?;
+            if init == AllocInit::Uninitialized {
+                unsafe { memory.ptr.as_ptr().write_bytes(ALLOC, memory.size) };
+            }
+            return Ok(memory);
+        }
+
+        let memory = self.alloc.alloc(layout, AllocInit::Uninitialized)?;
+        unsafe {
+            self.reuse(memory, 0);
+            match init {
+                AllocInit::Uninitialized => memory.ptr.as_ptr().write_bytes(ALLOC, memory.size),
+                AllocInit::Zeroed => init.init(memory),
+            }
+        }
+        Ok(memory)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        ptr.as_ptr().write_bytes(DEALLOC, layout.size());
+        self.alloc.dealloc(ptr, layout);
+        self.record_freed(ptr, layout.size());
+    }
+
+    #[track_caller]
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        if !self.verifies() {
+            let memory = self.alloc.grow(ptr, layout, new_size, placement, init)?;
+            if init == AllocInit::Uninitialized {
+                memory
+                    .ptr
+                    .as_ptr()
+                    .add(layout.size())
+                    .write_bytes(ALLOC, memory.size - layout.size());
+            }
+            return Ok(memory);
+        }
+
+        // The first `layout.size()` bytes hold the old content, only the rest may be freed memory
+        let memory = self
+            .alloc
+            .grow(ptr, layout, new_size, placement, AllocInit::Uninitialized)?;
+        self.reuse(memory, layout.size());
+        match init {
+            AllocInit::Uninitialized => memory
+                .ptr
+                .as_ptr()
+                .add(layout.size())
+                .write_bytes(ALLOC, memory.size - layout.size()),
+            AllocInit::Zeroed => init.init_offset(memory, layout.size()),
+        }
+        Ok(memory)
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let memory = self.alloc.shrink(ptr, layout, new_size, placement)?;
+        if memory.ptr == ptr && memory.size < layout.size() {
+            let tail = NonNull::new_unchecked(ptr.as_ptr().add(memory.size));
+            tail.as_ptr()
+                .write_bytes(DEALLOC, layout.size() - memory.size);
+            self.record_freed(tail, layout.size() - memory.size);
+        }
+        Ok(memory)
+    }
+}
+
+impl<A: Owns, const ALLOC: u8, const DEALLOC: u8> Owns for PatternMarker<A, ALLOC, DEALLOC> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.alloc.owns(memory)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::MemoryMarker;
+    use super::{MemoryMarker, PatternMarker};
     use crate::{
         helper::{self, AsSlice},
         Region,
     };
-    use std::alloc::{AllocInit, AllocRef, Layout, ReallocPlacement, System};
+    use core::ptr::NonNull;
+    use std::alloc::{
+        AllocErr,
+        AllocInit,
+        AllocRef,
+        Layout,
+        MemoryBlock,
+        ReallocPlacement,
+        System,
+    };
 
     #[test]
     fn alloc() {
@@ -152,4 +463,200 @@ mod tests {
             assert_eq!(data, [0, 0, 0, 0, 0xDD, 0xDD, 0xDD, 0xDD]);
         }
     }
+
+    #[test]
+    fn shrink_failed() {
+        /// Forwards to `System`, but can't grow or shrink in place.
+        struct MoveOnly;
+
+        unsafe impl AllocRef for MoveOnly {
+            fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+                System.alloc(layout, init)
+            }
+
+            unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+        }
+
+        fn test(mut alloc: impl AllocRef) {
+            let layout = Layout::new::<[u8; 8]>();
+            let memory = alloc
+                .alloc(layout, AllocInit::Zeroed)
+                .expect("Could not allocate 8 bytes");
+            unsafe {
+                alloc
+                    .shrink(memory.ptr, layout, 4, ReallocPlacement::InPlace)
+                    .expect_err("Could shrink to 4 bytes in place");
+                assert_eq!(memory.as_slice(), &[0; 8][..]);
+                alloc.dealloc(memory.ptr, layout);
+            }
+        }
+
+        test(MemoryMarker(MoveOnly));
+        test(PatternMarker::<_, 0xAA, 0xBB>::new(MoveOnly));
+    }
+
+    #[test]
+    fn patterns() {
+        let mut data = [0; 8];
+        let mut alloc = PatternMarker::<_, 0xAA, 0xBB>::new(Region::new(&mut data));
+        let memory = alloc
+            .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        unsafe {
+            assert_eq!(memory.as_slice(), &[0xAA; 8][..]);
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 8]>());
+        }
+        assert_eq!(data, [0xBB; 8]);
+    }
+
+    #[cfg(feature = "alloc")]
+    mod verify {
+        use super::*;
+        use crate::FreeList;
+        use core::mem;
+
+        type Marker<A> = PatternMarker<A, 0xCD, 0xDD>;
+
+        #[test]
+        fn region() {
+            let mut data = [0; 16];
+            let mut alloc = Marker::verifying(Region::new(&mut data), 0);
+            assert!(alloc.is_verifying());
+            let layout = Layout::new::<[u8; 8]>();
+
+            unsafe {
+                let memory = alloc
+                    .alloc(layout, AllocInit::Zeroed)
+                    .expect("Could not allocate 8 bytes");
+                assert_eq!(memory.as_slice(), &[0; 8][..]);
+                alloc.dealloc(memory.ptr, layout);
+
+                let memory = alloc
+                    .alloc(layout, AllocInit::Uninitialized)
+                    .expect("Could not allocate 8 bytes");
+                assert_eq!(memory.as_slice(), &[0xCD; 8][..]);
+
+                let memory = alloc
+                    .grow(
+                        memory.ptr,
+                        layout,
+                        16,
+                        ReallocPlacement::InPlace,
+                        AllocInit::Uninitialized,
+                    )
+                    .expect("Could not grow to 16 bytes");
+                assert_eq!(memory.as_slice(), &[0xCD; 16][..]);
+                alloc.dealloc(memory.ptr, Layout::new::<[u8; 16]>());
+            }
+        }
+
+        #[test]
+        fn merge() {
+            let mut data = [0; 24];
+            let mut alloc = Marker::verifying(Region::new(&mut data), 0);
+            let layout = Layout::new::<[u8; 8]>();
+
+            let mut blocks = [None; 3];
+            for block in &mut blocks {
+                *block = Some(
+                    alloc
+                        .alloc(layout, AllocInit::Uninitialized)
+                        .expect("Could not allocate 8 bytes"),
+                );
+            }
+            let ranges = |alloc: &Marker<_>| alloc.freed.as_ref().unwrap().ranges.len();
+            unsafe {
+                alloc.dealloc(blocks[0].unwrap().ptr, layout);
+                alloc.dealloc(blocks[2].unwrap().ptr, layout);
+                assert_eq!(ranges(&alloc), 2);
+                alloc.dealloc(blocks[1].unwrap().ptr, layout);
+                assert_eq!(ranges(&alloc), 1);
+            }
+        }
+
+        #[test]
+        fn free_list() {
+            let free_list = FreeList::<_, 8, 16>::new(System);
+            let mut alloc = Marker::verifying(free_list, mem::size_of::<usize>());
+            let layout = Layout::new::<[u8; 16]>();
+
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 16 bytes");
+            unsafe { alloc.dealloc(memory.ptr, layout) };
+            assert_eq!(alloc.alloc.len(), 1);
+
+            let recycled = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 16 bytes");
+            assert_eq!(recycled.ptr, memory.ptr);
+            unsafe {
+                assert_eq!(recycled.as_slice(), &[0xCD; 16][..]);
+                alloc.dealloc(recycled.ptr, layout);
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "was written to at offset 12")]
+        fn free_list_use_after_free() {
+            let free_list = FreeList::<_, 8, 16>::new(System);
+            let mut alloc = Marker::verifying(free_list, mem::size_of::<usize>());
+            let layout = Layout::new::<[u8; 16]>();
+
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 16 bytes");
+            unsafe {
+                alloc.dealloc(memory.ptr, layout);
+                memory.ptr.as_ptr().add(12).write(0);
+            }
+            let _ = alloc.alloc(layout, AllocInit::Uninitialized);
+        }
+
+        #[test]
+        #[should_panic(expected = "was written to at offset 5")]
+        fn use_after_free() {
+            let mut data = [0; 8];
+            let mut alloc = Marker::verifying(Region::new(&mut data), 0);
+            let layout = Layout::new::<[u8; 8]>();
+
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            unsafe {
+                alloc.dealloc(memory.ptr, layout);
+                memory.ptr.as_ptr().add(5).write(0);
+            }
+            let _ = alloc.alloc(layout, AllocInit::Uninitialized);
+        }
+
+        #[test]
+        #[should_panic(expected = "was written to at offset 12")]
+        fn grow_in_place() {
+            let mut data = [0; 16];
+            let mut alloc = Marker::verifying(Region::new(&mut data), 0);
+            let layout = Layout::new::<[u8; 8]>();
+            let tail = Layout::new::<[u8; 8]>();
+
+            unsafe {
+                let memory = alloc
+                    .alloc(layout, AllocInit::Uninitialized)
+                    .expect("Could not allocate 8 bytes");
+                let next = alloc
+                    .alloc(tail, AllocInit::Uninitialized)
+                    .expect("Could not allocate 8 bytes");
+                alloc.dealloc(next.ptr, tail);
+                next.ptr.as_ptr().add(4).write(0);
+                let _ = alloc.grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,267 @@
+use crate::{Owns, Region, RegionCheckpoint};
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    fmt,
+    ptr::NonNull,
+    slice,
+};
+
+/// Allocator over a region of memory, which is allocated from a parent allocator.
+///
+/// In contrast to [`Region`], `OwnedRegion` does not borrow its memory, so it can be stored in
+/// structs without a lifetime. The memory is requested from the parent allocator on construction
+/// and is returned when `OwnedRegion` is dropped.
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{OwnedRegion, Owns};
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+/// use std::alloc::System;
+///
+/// let mut region = OwnedRegion::new(System, Layout::new::<[u8; 64]>())?;
+/// assert_eq!(region.capacity(), 64);
+///
+/// let memory = region.alloc(Layout::new::<u32>(), AllocInit::Uninitialized)?;
+/// assert!(region.owns(memory));
+/// assert_eq!(region.capacity_left(), 60);
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct OwnedRegion<A: AllocRef> {
+    // The lifetime is bound to `self`, the memory is deallocated in `Drop`
+    region: Region<'static>,
+    ptr: NonNull<u8>,
+    layout: Layout,
+    parent: A,
+}
+
+impl<A: AllocRef> OwnedRegion<A> {
+    /// Allocates memory fitting `layout` from `parent` and creates a region over it.
+    ///
+    /// The capacity may be greater than `layout.size()` if the parent returns a larger block.
+    pub fn new(mut parent: A, layout: Layout) -> Result<Self, AllocErr> {
+        let memory = parent.alloc(layout, AllocInit::Zeroed)?;
+        let data = unsafe { slice::from_raw_parts_mut(memory.ptr.as_ptr(), memory.size) };
+        Ok(Self {
+            region: Region::new(data),
+            ptr: memory.ptr,
+            layout,
+            parent,
+        })
+    }
+
+    /// Returns a reference to the parent allocator.
+    pub const fn parent(&self) -> &A {
+        &self.parent
+    }
+
+    /// Returns the total capacity available in this allocator.
+    pub const fn capacity(&self) -> usize {
+        self.region.capacity()
+    }
+
+    /// Returns the free capacity left for allocating.
+    pub fn capacity_left(&self) -> usize {
+        self.region.capacity_left()
+    }
+
+    /// Resets the allocator.
+    ///
+    /// This implies, that `capacity()` and `capacity_left()` are equal afterwards.
+    pub fn reset(&mut self) {
+        self.region.reset()
+    }
+
+    /// Checks if `memory` is the latest block, which was allocated.
+    /// For those blocks, it's possible to deallocate them or to grow
+    /// or shrink them in place.
+    #[inline]
+    pub fn is_last_block(&self, memory: MemoryBlock) -> bool {
+        self.region.is_last_block(memory)
+    }
+
+    /// Returns the maximum size, the block at `ptr` can be grown to in place.
+    ///
+    /// See [`Region::grow_in_place_max`] for more information.
+    #[inline]
+    pub fn grow_in_place_max(&self, ptr: NonNull<u8>, layout: Layout) -> usize {
+        self.region.grow_in_place_max(ptr, layout)
+    }
+
+    /// Grows the latest block in place and hands out the padding up to the next alignment
+    /// boundary as well.
+    ///
+    /// See [`Region::grow_with_slack`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// See [`Region::grow_with_slack`].
+    ///
+    /// # Safety
+    ///
+    /// See [`Region::grow_with_slack`].
+    pub unsafe fn grow_with_slack(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        self.region.grow_with_slack(ptr, layout, new_size, init)
+    }
+
+    /// Saves the current position of the allocator.
+    ///
+    /// See [`Region::checkpoint`] for more information.
+    #[inline]
+    pub fn checkpoint(&mut self) -> RegionCheckpoint {
+        self.region.checkpoint()
+    }
+
+    /// Frees all memory, which was allocated after `checkpoint` was created.
+    ///
+    /// # Safety
+    ///
+    /// See [`Region::rewind`].
+    pub unsafe fn rewind(&mut self, checkpoint: RegionCheckpoint) {
+        self.region.rewind(checkpoint)
+    }
+}
+
+impl<A: AllocRef> Drop for OwnedRegion<A> {
+    fn drop(&mut self) {
+        unsafe { self.parent.dealloc(self.ptr, self.layout) }
+    }
+}
+
+impl<A: AllocRef> fmt::Debug for OwnedRegion<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OwnedRegion")
+            .field("capacity", &self.capacity())
+            .field("capacity_left", &self.capacity_left())
+            .finish()
+    }
+}
+
+unsafe impl<A: AllocRef> AllocRef for OwnedRegion<A> {
+    #[inline]
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        self.region.alloc(layout, init)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        self.region.dealloc(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        self.region.grow(ptr, layout, new_size, placement, init)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        self.region.shrink(ptr, layout, new_size, placement)
+    }
+}
+
+impl<A: AllocRef> Owns for OwnedRegion<A> {
+    #[inline]
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.region.owns(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::{helper, NullAlloc};
+    use std::alloc::System;
+
+    #[test]
+    fn new() {
+        let region = OwnedRegion::new(helper::tracker(System), Layout::new::<[u8; 32]>())
+            .expect("Could not allocate 32 bytes");
+        assert_eq!(region.capacity(), 32);
+        assert_eq!(region.capacity(), region.capacity_left());
+
+        OwnedRegion::new(NullAlloc, Layout::new::<[u8; 32]>())
+            .expect_err("Could allocate 32 bytes with `NullAlloc`");
+    }
+
+    #[test]
+    fn alloc() {
+        let mut region = OwnedRegion::new(helper::tracker(System), Layout::new::<[u8; 32]>())
+            .expect("Could not allocate 32 bytes");
+        let layout = Layout::new::<[u8; 8]>();
+
+        let memory = region
+            .alloc(layout, AllocInit::Zeroed)
+            .expect("Could not allocate 8 bytes");
+        assert!(region.owns(memory));
+        assert!(region.is_last_block(memory));
+        assert_eq!(region.capacity_left(), 24);
+
+        region
+            .alloc(Layout::new::<[u8; 32]>(), AllocInit::Uninitialized)
+            .expect_err("Could allocate 32 bytes");
+
+        unsafe { region.dealloc(memory.ptr, layout) };
+        assert_eq!(region.capacity_left(), 32);
+        assert!(!region.owns(memory));
+    }
+
+    #[test]
+    fn realloc() {
+        let mut region = OwnedRegion::new(helper::tracker(System), Layout::new::<[u8; 32]>())
+            .expect("Could not allocate 32 bytes");
+        let layout = Layout::new::<[u8; 8]>();
+
+        let memory = region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let memory = unsafe {
+            region
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 16 bytes")
+        };
+        assert_eq!(region.capacity_left(), 16);
+
+        unsafe {
+            region
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    8,
+                    ReallocPlacement::InPlace,
+                )
+                .expect("Could not shrink to 8 bytes")
+        };
+        assert_eq!(region.capacity_left(), 24);
+
+        region.reset();
+        assert_eq!(region.capacity_left(), region.capacity());
+    }
+}
@@ -0,0 +1,292 @@
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    ptr::{self, NonNull},
+};
+
+/// Allocates memory directly from the operating system with `mmap`.
+///
+/// All requests are rounded up to a multiple of the page size, the returned [`MemoryBlock`]
+/// reports the rounded size. Zero-sized requests don't map any memory. Alignments greater than
+/// the page size are not supported.
+///
+/// On Linux, `grow` uses `mremap` to resize the mapping in place if possible. `shrink` always
+/// succeeds in place by unmapping the trailing pages.
+///
+/// As `PageAlloc` doesn't have any state, it can't determine if it owns a block of memory.
+///
+/// ## Examples
+///
+/// `PageAlloc` is a useful parent for allocators requesting large chunks of memory:
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{OwnedRegion, PageAlloc};
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+///
+/// let mut region = OwnedRegion::new(PageAlloc, Layout::new::<[u8; 100]>())?;
+/// assert_eq!(region.capacity(), PageAlloc::page_size());
+///
+/// region.alloc(Layout::new::<[u8; 100]>(), AllocInit::Uninitialized)?;
+/// assert_eq!(region.capacity_left(), PageAlloc::page_size() - 100);
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PageAlloc;
+
+impl PageAlloc {
+    /// Returns the page size of the system.
+    pub fn page_size() -> usize {
+        unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+    }
+
+    /// Rounds `size` up to the next multiple of the page size.
+    fn round_up(size: usize) -> Option<usize> {
+        let page_size = Self::page_size();
+        Some(size.checked_add(page_size - 1)? & !(page_size - 1))
+    }
+
+    fn dangling(layout: Layout) -> MemoryBlock {
+        MemoryBlock {
+            ptr: unsafe { NonNull::new_unchecked(layout.align() as *mut u8) },
+            size: 0,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    unsafe fn remap(
+        ptr: NonNull<u8>,
+        old_len: usize,
+        new_len: usize,
+        placement: ReallocPlacement,
+    ) -> Result<NonNull<u8>, AllocErr> {
+        let flags = match placement {
+            ReallocPlacement::MayMove => libc::MREMAP_MAYMOVE,
+            ReallocPlacement::InPlace => 0,
+        };
+        let ptr = libc::mremap(ptr.as_ptr().cast(), old_len, new_len, flags);
+        if ptr == libc::MAP_FAILED {
+            Err(AllocErr)
+        } else {
+            Ok(NonNull::new_unchecked(ptr.cast()))
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    unsafe fn remap(
+        ptr: NonNull<u8>,
+        old_len: usize,
+        new_len: usize,
+        placement: ReallocPlacement,
+    ) -> Result<NonNull<u8>, AllocErr> {
+        if placement == ReallocPlacement::InPlace {
+            return Err(AllocErr);
+        }
+        let new_layout = Layout::from_size_align_unchecked(new_len, Self::page_size());
+        let new_memory = Self.alloc(new_layout, AllocInit::Uninitialized)?;
+        ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), old_len);
+        libc::munmap(ptr.as_ptr().cast(), old_len);
+        Ok(new_memory.ptr)
+    }
+}
+
+unsafe impl AllocRef for PageAlloc {
+    fn alloc(&mut self, layout: Layout, _init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        if layout.align() > Self::page_size() {
+            return Err(AllocErr);
+        }
+        if layout.size() == 0 {
+            return Ok(Self::dangling(layout));
+        }
+        let len = Self::round_up(layout.size()).ok_or(AllocErr)?;
+
+        // Anonymous mappings are zero-initialized, so `init` can be ignored
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(AllocErr);
+        }
+        Ok(MemoryBlock {
+            ptr: unsafe { NonNull::new_unchecked(ptr.cast()) },
+            size: len,
+        })
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            let len = Self::round_up(layout.size()).expect("Invalid layout");
+            libc::munmap(ptr.as_ptr().cast(), len);
+        }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size >= layout.size(),
+            "`new_size` must be greater than or equal to `layout.size()`"
+        );
+
+        let old_len = Self::round_up(layout.size()).ok_or(AllocErr)?;
+        let new_len = Self::round_up(new_size).ok_or(AllocErr)?;
+
+        // The tail of the last page may have been written to, new pages are zeroed by the system
+        if init == AllocInit::Zeroed {
+            ptr.as_ptr()
+                .add(layout.size())
+                .write_bytes(0, old_len - layout.size());
+        }
+
+        if new_len == old_len {
+            Ok(MemoryBlock { ptr, size: new_len })
+        } else if old_len == 0 {
+            if placement == ReallocPlacement::InPlace {
+                return Err(AllocErr);
+            }
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            self.alloc(new_layout, init)
+        } else {
+            Ok(MemoryBlock {
+                ptr: Self::remap(ptr, old_len, new_len, placement)?,
+                size: new_len,
+            })
+        }
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        _placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size <= layout.size(),
+            "`new_size` must be smaller than or equal to `layout.size()`"
+        );
+
+        let old_len = Self::round_up(layout.size()).ok_or(AllocErr)?;
+        let new_len = Self::round_up(new_size).ok_or(AllocErr)?;
+        if new_len != old_len {
+            libc::munmap(ptr.as_ptr().add(new_len).cast(), old_len - new_len);
+        }
+        Ok(MemoryBlock { ptr, size: new_len })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::helper::{self, AsSlice};
+
+    #[test]
+    fn alloc() {
+        let page_size = PageAlloc::page_size();
+        let mut alloc = helper::tracker(PageAlloc);
+
+        for &size in &[0, 1, page_size, page_size + 1] {
+            let layout = Layout::from_size_align(size, 8).expect("Invalid layout");
+            let memory = alloc
+                .alloc(layout, AllocInit::Zeroed)
+                .unwrap_or_else(|_| panic!("Could not allocate {} bytes", size));
+            assert_eq!(memory.size % page_size, 0);
+            assert!(memory.size >= size);
+            unsafe {
+                assert_eq!(memory.as_slice(), &vec![0; memory.size][..]);
+                alloc.dealloc(memory.ptr, layout);
+            }
+        }
+
+        alloc
+            .alloc(
+                Layout::from_size_align(8, page_size * 2).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect_err("Could allocate with an alignment greater than the page size");
+    }
+
+    #[test]
+    fn grow() {
+        let page_size = PageAlloc::page_size();
+        let mut alloc = helper::tracker(PageAlloc);
+        let layout = Layout::new::<[u8; 8]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.as_slice_mut().iter_mut().for_each(|byte| *byte = 1);
+
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(memory.size, page_size);
+            assert_eq!(&memory.as_slice()[..8], &[1; 8][..]);
+            assert!(memory.as_slice()[8..].iter().all(|&byte| byte == 0));
+
+            let new_memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    page_size * 4,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 4 pages");
+            assert_eq!(new_memory.size, page_size * 4);
+            assert_eq!(&new_memory.as_slice()[..8], &[1; 8][..]);
+            assert!(new_memory.as_slice()[8..].iter().all(|&byte| byte == 0));
+
+            alloc.dealloc(
+                new_memory.ptr,
+                Layout::from_size_align(page_size * 4, 1).expect("Invalid layout"),
+            );
+        }
+    }
+
+    #[test]
+    fn shrink() {
+        let page_size = PageAlloc::page_size();
+        let mut alloc = helper::tracker(PageAlloc);
+        let layout = Layout::from_size_align(page_size * 4, 8).expect("Invalid layout");
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 4 pages");
+            memory.as_slice_mut()[..8].copy_from_slice(&[1; 8]);
+
+            let new_memory = alloc
+                .shrink(memory.ptr, layout, 100, ReallocPlacement::InPlace)
+                .expect("Could not shrink to 100 bytes");
+            assert_eq!(new_memory.ptr, memory.ptr);
+            assert_eq!(new_memory.size, page_size);
+            assert_eq!(&new_memory.as_slice()[..8], &[1; 8][..]);
+
+            alloc.dealloc(
+                new_memory.ptr,
+                Layout::from_size_align(100, 8).expect("Invalid layout"),
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{CallbackRef, Owns};
 use core::{
     alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    panic::Location,
     ptr::NonNull,
 };
 
@@ -79,15 +80,17 @@ pub struct Proxy<A, C> {
 }
 
 unsafe impl<A: AllocRef, C: CallbackRef> AllocRef for Proxy<A, C> {
+    #[track_caller]
     fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
         let result = self.alloc.alloc(layout, init);
-        self.callbacks.alloc(layout, init, result.clone());
+        self.callbacks
+            .alloc(layout, init, result.clone(), Location::caller());
         result
     }
 
     #[track_caller]
     unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
-        self.callbacks.dealloc(ptr, layout);
+        self.callbacks.dealloc(ptr, layout, Location::caller());
         self.alloc.dealloc(ptr, layout)
     }
 
@@ -101,8 +104,15 @@ unsafe impl<A: AllocRef, C: CallbackRef> AllocRef for Proxy<A, C> {
         init: AllocInit,
     ) -> Result<MemoryBlock, AllocErr> {
         let result = self.alloc.grow(ptr, layout, new_size, placement, init);
-        self.callbacks
-            .grow(ptr, layout, new_size, placement, init, result.clone());
+        self.callbacks.grow(
+            ptr,
+            layout,
+            new_size,
+            placement,
+            init,
+            result.clone(),
+            Location::caller(),
+        );
         result
     }
 
@@ -115,8 +125,14 @@ unsafe impl<A: AllocRef, C: CallbackRef> AllocRef for Proxy<A, C> {
         placement: ReallocPlacement,
     ) -> Result<MemoryBlock, AllocErr> {
         let result = self.alloc.shrink(ptr, layout, new_size, placement);
-        self.callbacks
-            .shrink(ptr, layout, new_size, placement, result.clone());
+        self.callbacks.shrink(
+            ptr,
+            layout,
+            new_size,
+            placement,
+            result.clone(),
+            Location::caller(),
+        );
         result
     }
 }
@@ -0,0 +1,300 @@
+use crate::Owns;
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    fmt,
+    ptr::NonNull,
+    slice,
+};
+
+/// Delays the reuse of deallocated memory.
+///
+/// Deallocated blocks are not returned to the parent allocator immediately, but are kept in a ring
+/// buffer of the last `N` deallocated blocks. Only when a block is pushed out of the buffer, it's
+/// deallocated with the parent. This makes use-after-free bugs more visible, as freed memory isn't
+/// handed out again right away.
+///
+/// Optionally, quarantined blocks can be poisoned with a byte pattern with [`poisoning`]. When a
+/// block leaves the quarantine, it's checked to still be filled with that pattern, which detects
+/// writes to freed memory. The check panics with the offset of the first corrupted byte.
+///
+/// All quarantined blocks are deallocated with [`flush`] or when the `Quarantine` is dropped.
+///
+/// [`poisoning`]: Self::poisoning
+/// [`flush`]: Self::flush
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::Quarantine;
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let mut alloc = Quarantine::<_, 4>::poisoning(System, 0xDD);
+///
+/// let memory = alloc.alloc(Layout::new::<u64>(), AllocInit::Uninitialized)?;
+/// unsafe { alloc.dealloc(memory.ptr, Layout::new::<u64>()) };
+/// assert_eq!(alloc.len(), 1);
+///
+/// alloc.flush();
+/// assert!(alloc.is_empty());
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct Quarantine<A: AllocRef, const N: usize> {
+    parent: A,
+    blocks: [Option<(NonNull<u8>, Layout)>; N],
+    next: usize,
+    poison: Option<u8>,
+}
+
+impl<A: AllocRef, const N: usize> Quarantine<A, N> {
+    /// Creates a new `Quarantine`, which doesn't touch the content of quarantined blocks.
+    pub const fn new(parent: A) -> Self {
+        Self {
+            parent,
+            blocks: [None; N],
+            next: 0,
+            poison: None,
+        }
+    }
+
+    /// Creates a new `Quarantine`, which fills quarantined blocks with `pattern` and verifies them
+    /// before they are deallocated with the parent.
+    pub const fn poisoning(parent: A, pattern: u8) -> Self {
+        Self {
+            parent,
+            blocks: [None; N],
+            next: 0,
+            poison: Some(pattern),
+        }
+    }
+
+    /// Returns a reference to the parent allocator.
+    pub const fn parent(&self) -> &A {
+        &self.parent
+    }
+
+    /// Returns the number of blocks in quarantine.
+    pub fn len(&self) -> usize {
+        self.blocks.iter().filter(|block| block.is_some()).count()
+    }
+
+    /// Returns `true` if no block is in quarantine.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().all(Option::is_none)
+    }
+
+    /// Deallocates all blocks in quarantine with the parent allocator.
+    pub fn flush(&mut self) {
+        for index in 0..N {
+            if let Some((ptr, layout)) = self.blocks[index].take() {
+                unsafe { self.release(ptr, layout) };
+            }
+        }
+        self.next = 0;
+    }
+
+    #[track_caller]
+    unsafe fn release(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        if let Some(pattern) = self.poison {
+            let memory = slice::from_raw_parts(ptr.as_ptr(), layout.size());
+            if let Some(offset) = memory.iter().position(|&byte| byte != pattern) {
+                panic!(
+                    "Quarantined memory at {:?} was written to at offset {}: expected {:#04X}, \
+                     found {:#04X}",
+                    ptr, offset, pattern, memory[offset]
+                );
+            }
+        }
+        self.parent.dealloc(ptr, layout)
+    }
+}
+
+impl<A: AllocRef, const N: usize> Drop for Quarantine<A, N> {
+    fn drop(&mut self) {
+        self.flush()
+    }
+}
+
+impl<A: AllocRef + fmt::Debug, const N: usize> fmt::Debug for Quarantine<A, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Quarantine")
+            .field("parent", &self.parent)
+            .field("len", &self.len())
+            .field("poison", &self.poison)
+            .finish()
+    }
+}
+
+unsafe impl<A: AllocRef, const N: usize> AllocRef for Quarantine<A, N> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        self.parent.alloc(layout, init)
+    }
+
+    #[track_caller]
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        if N == 0 {
+            return self.parent.dealloc(ptr, layout);
+        }
+        if let Some(pattern) = self.poison {
+            ptr.as_ptr().write_bytes(pattern, layout.size());
+        }
+        if let Some((old_ptr, old_layout)) = self.blocks[self.next].replace((ptr, layout)) {
+            self.release(old_ptr, old_layout);
+        }
+        self.next = (self.next + 1) % N;
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        self.parent.grow(ptr, layout, new_size, placement, init)
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        self.parent.shrink(ptr, layout, new_size, placement)
+    }
+}
+
+impl<A: AllocRef + Owns, const N: usize> Owns for Quarantine<A, N> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.parent.owns(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::{helper, stats::Counter, CallbackRef, Proxy, Region};
+    use std::alloc::System;
+
+    #[test]
+    fn dealloc() {
+        let counter = Counter::default();
+        let mut alloc = Quarantine::<_, 2>::new(Proxy {
+            alloc: helper::tracker(System),
+            callbacks: counter.by_ref(),
+        });
+        let layout = Layout::new::<u64>();
+
+        let blocks = (0..3)
+            .map(|_| {
+                alloc
+                    .alloc(layout, AllocInit::Uninitialized)
+                    .expect("Could not allocate 8 bytes")
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            alloc.dealloc(blocks[0].ptr, layout);
+            alloc.dealloc(blocks[1].ptr, layout);
+            assert_eq!(alloc.len(), 2);
+            assert_eq!(counter.num_deallocs(), 0);
+
+            alloc.dealloc(blocks[2].ptr, layout);
+            assert_eq!(alloc.len(), 2);
+            assert_eq!(counter.num_deallocs(), 1);
+        }
+
+        alloc.flush();
+        assert!(alloc.is_empty());
+        assert_eq!(counter.num_deallocs(), 3);
+    }
+
+    #[test]
+    fn drop() {
+        let counter = Counter::default();
+        let mut alloc = Quarantine::<_, 4>::new(Proxy {
+            alloc: helper::tracker(System),
+            callbacks: counter.by_ref(),
+        });
+        let layout = Layout::new::<u64>();
+
+        let memory = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        unsafe { alloc.dealloc(memory.ptr, layout) };
+        assert_eq!(counter.num_deallocs(), 0);
+
+        core::mem::drop(alloc);
+        assert_eq!(counter.num_deallocs(), 1);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let counter = Counter::default();
+        let mut alloc = Quarantine::<_, 0>::new(Proxy {
+            alloc: System,
+            callbacks: counter.by_ref(),
+        });
+        let layout = Layout::new::<u64>();
+
+        let memory = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        unsafe { alloc.dealloc(memory.ptr, layout) };
+        assert!(alloc.is_empty());
+        assert_eq!(counter.num_deallocs(), 1);
+    }
+
+    #[test]
+    fn owns() {
+        let mut data = [0; 32];
+        let mut alloc = Quarantine::<_, 2>::new(Region::new(&mut data));
+        let layout = Layout::new::<[u8; 8]>();
+
+        let memory = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        assert!(alloc.owns(memory));
+
+        unsafe { alloc.dealloc(memory.ptr, layout) };
+        // The block is still owned by the parent until it leaves the quarantine
+        assert!(alloc.owns(memory));
+        alloc.flush();
+        assert!(!alloc.owns(memory));
+    }
+
+    #[test]
+    fn poison() {
+        let mut alloc = Quarantine::<_, 2>::poisoning(helper::tracker(System), 0xDD);
+        let layout = Layout::new::<[u8; 8]>();
+
+        let memory = alloc
+            .alloc(layout, AllocInit::Zeroed)
+            .expect("Could not allocate 8 bytes");
+        unsafe {
+            alloc.dealloc(memory.ptr, layout);
+            assert_eq!(memory.ptr.as_ptr().read(), 0xDD);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "was written to at offset 3")]
+    fn use_after_free() {
+        let mut data = [0; 8];
+        let mut alloc = Quarantine::<_, 2>::poisoning(Region::new(&mut data), 0xDD);
+        let layout = Layout::new::<[u8; 8]>();
+
+        let memory = alloc
+            .alloc(layout, AllocInit::Zeroed)
+            .expect("Could not allocate 8 bytes");
+        unsafe {
+            alloc.dealloc(memory.ptr, layout);
+            memory.ptr.as_ptr().add(3).write(0);
+        }
+        alloc.flush();
+    }
+}
@@ -39,6 +39,16 @@ use core::{
 pub struct Region<'a> {
     data: &'a mut [u8],
     offset: usize,
+    /// The number of checkpoints, which may still be rewound.
+    #[cfg(debug_assertions)]
+    checkpoints: usize,
+    /// The number of checkpoints created so far.
+    #[cfg(debug_assertions)]
+    generation: usize,
+    /// The generation and depth of the latest `rewind` or `reset`. All checkpoints of an older
+    /// generation at this depth or deeper are invalidated.
+    #[cfg(debug_assertions)]
+    invalidated: (usize, usize),
 }
 
 impl<'a> Region<'a> {
@@ -48,6 +58,12 @@ impl<'a> Region<'a> {
         Self {
             data,
             offset: current,
+            #[cfg(debug_assertions)]
+            checkpoints: 0,
+            #[cfg(debug_assertions)]
+            generation: 0,
+            #[cfg(debug_assertions)]
+            invalidated: (0, 0),
         }
     }
 
@@ -66,6 +82,11 @@ impl<'a> Region<'a> {
     /// This implies, that `capacity()` and `capacity_left()` are equal afterwards.
     pub fn reset(&mut self) {
         self.offset = self.data.as_ptr() as usize;
+        #[cfg(debug_assertions)]
+        {
+            self.checkpoints = 0;
+            self.invalidated = (self.generation, 0);
+        }
         debug_assert_eq!(self.capacity(), self.capacity_left());
     }
 
@@ -76,6 +97,216 @@ impl<'a> Region<'a> {
     pub fn is_last_block(&self, memory: MemoryBlock) -> bool {
         memory.ptr.as_ptr() as usize + memory.size == self.offset as usize
     }
+
+    /// Returns the maximum size, the block at `ptr` can be grown to in place.
+    ///
+    /// For the latest block, this is the size up to the end of the region. Other blocks can't be
+    /// grown in place, so `layout.size()` is returned.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// #![feature(allocator_api)]
+    ///
+    /// use alloc_compose::Region;
+    /// use core::alloc::{AllocInit, AllocRef, Layout};
+    ///
+    /// let mut data = [0; 64];
+    /// let mut region = Region::new(&mut data);
+    ///
+    /// let first = region.alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)?;
+    /// assert_eq!(region.grow_in_place_max(first.ptr, Layout::new::<[u8; 8]>()), 64);
+    ///
+    /// region.alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)?;
+    /// assert_eq!(region.grow_in_place_max(first.ptr, Layout::new::<[u8; 8]>()), 8);
+    /// # Ok::<(), core::alloc::AllocErr>(())
+    /// ```
+    pub fn grow_in_place_max(&self, ptr: NonNull<u8>, layout: Layout) -> usize {
+        if self.is_last_block(MemoryBlock {
+            ptr,
+            size: layout.size(),
+        }) {
+            self.data.as_ptr() as usize + self.data.len() - ptr.as_ptr() as usize
+        } else {
+            layout.size()
+        }
+    }
+
+    /// Grows the latest block in place and hands out the padding up to the next multiple of
+    /// `layout.align()` as well.
+    ///
+    /// The returned block is limited by the remaining capacity. As the padding is part of the
+    /// block, it can't be used by following allocations with a smaller alignment. In order to
+    /// deallocate, grow, or shrink the block in place afterwards, `memory.size` of the returned
+    /// block has to be passed as size of the layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AllocErr`, if the block at `ptr` is not the latest block or if the remaining
+    /// capacity is too small.
+    ///
+    /// # Safety
+    ///
+    /// The same restrictions as for [`AllocRef::grow`] apply.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// #![feature(allocator_api)]
+    ///
+    /// use alloc_compose::Region;
+    /// use core::alloc::{AllocInit, AllocRef, Layout};
+    ///
+    /// let mut data = [0; 64];
+    /// let mut region = Region::new(&mut data);
+    ///
+    /// let layout = Layout::new::<u32>();
+    /// let memory = region.alloc(layout, AllocInit::Uninitialized)?;
+    /// let memory = unsafe { region.grow_with_slack(memory.ptr, layout, 5, AllocInit::Zeroed)? };
+    /// assert_eq!(memory.size, 8);
+    /// # Ok::<(), core::alloc::AllocErr>(())
+    /// ```
+    pub unsafe fn grow_with_slack(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        debug_assert!(
+            new_size >= layout.size(),
+            "`new_size` must be greater than or equal to `layout.size()`"
+        );
+        if !self.is_last_block(MemoryBlock {
+            ptr,
+            size: layout.size(),
+        }) {
+            return Err(AllocErr);
+        }
+        let max_size = self.grow_in_place_max(ptr, layout);
+        if new_size > max_size {
+            return Err(AllocErr);
+        }
+        let align_mask = layout.align() - 1;
+        let new_size = match new_size.checked_add(align_mask) {
+            Some(size) if size & !align_mask <= max_size => size & !align_mask,
+            _ => max_size,
+        };
+        self.grow_last_block(ptr, layout, new_size, init)
+    }
+
+    unsafe fn grow_last_block(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let start = ptr.as_ptr() as usize;
+        let new = start.checked_add(new_size).ok_or(AllocErr)?;
+        if new > self.data.as_ptr() as usize + self.data.len() {
+            return Err(AllocErr);
+        }
+        self.offset = new;
+        let new_memory = MemoryBlock {
+            ptr,
+            size: new_size,
+        };
+        init.init_offset(new_memory, layout.size());
+        Ok(new_memory)
+    }
+
+    /// Saves the current position of the allocator.
+    ///
+    /// All memory allocated after the checkpoint was created can be freed at once by passing the
+    /// checkpoint to [`rewind`].
+    ///
+    /// [`rewind`]: Self::rewind
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// #![feature(allocator_api)]
+    ///
+    /// use alloc_compose::Region;
+    /// use core::alloc::{AllocInit, AllocRef, Layout};
+    ///
+    /// let mut data = [0; 64];
+    /// let mut region = Region::new(&mut data);
+    /// region.alloc(Layout::new::<[u8; 4]>(), AllocInit::Uninitialized)?;
+    ///
+    /// let checkpoint = region.checkpoint();
+    /// region.alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)?;
+    /// region.alloc(Layout::new::<[u8; 32]>(), AllocInit::Uninitialized)?;
+    /// assert_eq!(region.capacity_left(), 12);
+    ///
+    /// unsafe { region.rewind(checkpoint) };
+    /// assert_eq!(region.capacity_left(), 60);
+    /// # Ok::<(), core::alloc::AllocErr>(())
+    /// ```
+    #[inline]
+    pub fn checkpoint(&mut self) -> RegionCheckpoint {
+        #[cfg(debug_assertions)]
+        {
+            self.checkpoints += 1;
+            self.generation += 1;
+        }
+        RegionCheckpoint {
+            offset: self.offset,
+            #[cfg(debug_assertions)]
+            depth: self.checkpoints - 1,
+            #[cfg(debug_assertions)]
+            generation: self.generation - 1,
+        }
+    }
+
+    /// Frees all memory, which was allocated after `checkpoint` was created.
+    ///
+    /// Checkpoints have to be rewound in the reverse order of their creation. Rewinding a
+    /// checkpoint invalidates all checkpoints created after it, and [`reset`] invalidates all
+    /// checkpoints. In debug builds, rewinding a checkpoint nested deeper than the valid ones, or
+    /// one invalidated by the latest `rewind` or `reset`, panics.
+    ///
+    /// [`reset`]: Self::reset
+    ///
+    /// # Safety
+    ///
+    /// * `checkpoint` must have been created by this allocator, and
+    /// * the memory allocated after `checkpoint` was created must not be used anymore.
+    pub unsafe fn rewind(&mut self, checkpoint: RegionCheckpoint) {
+        debug_assert!(
+            checkpoint.offset >= self.data.as_ptr() as usize
+                && checkpoint.offset <= self.data.as_ptr() as usize + self.data.len(),
+            "`checkpoint` must have been created by this allocator"
+        );
+        #[cfg(debug_assertions)]
+        {
+            let (generation, depth) = self.invalidated;
+            assert!(
+                checkpoint.depth < self.checkpoints
+                    && (checkpoint.generation >= generation || checkpoint.depth < depth),
+                "Checkpoints must be rewound in the reverse order of their creation"
+            );
+            self.checkpoints = checkpoint.depth;
+            self.invalidated = (self.generation, checkpoint.depth);
+        }
+        self.offset = checkpoint.offset;
+    }
+}
+
+/// A saved position of a [`Region`].
+///
+/// See [`Region::checkpoint`] for more information.
+#[derive(Debug, PartialEq, Eq)]
+#[must_use = "A checkpoint has no effect unless it's passed to `rewind`"]
+pub struct RegionCheckpoint {
+    offset: usize,
+    /// The number of checkpoints created before this one, which may still be rewound.
+    #[cfg(debug_assertions)]
+    depth: usize,
+    /// The number of checkpoints created before this one.
+    #[cfg(debug_assertions)]
+    generation: usize,
 }
 
 impl fmt::Debug for Region<'_> {
@@ -146,22 +377,13 @@ unsafe impl AllocRef for Region<'_> {
             ptr,
             size: layout.size(),
         }) {
-            let start = ptr.as_ptr() as usize;
-            let new = start.checked_add(new_size).ok_or(AllocErr)?;
-            if new > self.data.as_ptr() as usize + self.data.len() {
-                return Err(AllocErr);
-            }
-            self.offset = new;
-            let new_memory = MemoryBlock {
-                ptr,
-                size: new_size,
-            };
-            init.init_offset(new_memory, layout.size());
-            Ok(new_memory)
+            self.grow_last_block(ptr, layout, new_size, init)
         } else if placement == ReallocPlacement::MayMove {
             let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
             let new_memory = self.alloc(new_layout, init)?;
             ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), size);
+            // The old block is followed by other blocks, so it can't be reclaimed until the
+            // region is rewound or reset
             Ok(new_memory)
         } else {
             Err(AllocErr)
@@ -440,4 +662,199 @@ mod tests {
         region.reset();
         assert_eq!(region.capacity_left(), region.capacity());
     }
+
+    #[test]
+    fn grow_aligned() {
+        let buffer_layout = Layout::from_size_align(32, 8).expect("Invalid layout");
+        let buffer = Global
+            .alloc(buffer_layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 32 bytes");
+        let mut region = Region::new(unsafe { buffer.as_slice_mut() });
+        let layout = Layout::from_size_align(4, 4).expect("Invalid layout");
+
+        let memory = region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 4 bytes");
+        assert_eq!(region.grow_in_place_max(memory.ptr, layout), 32);
+
+        unsafe {
+            // `grow` doesn't hand out any slack, so the requested size can be deallocated
+            let grown = region
+                .grow(
+                    memory.ptr,
+                    layout,
+                    9,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 9 bytes");
+            assert_eq!(grown.size, 9);
+            assert_eq!(region.capacity_left(), 23);
+            region.dealloc(grown.ptr, Layout::from_size_align_unchecked(9, 4));
+            assert_eq!(region.capacity_left(), 32);
+        }
+
+        let memory = region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 4 bytes");
+        let memory = unsafe {
+            region
+                .grow_with_slack(memory.ptr, layout, 9, AllocInit::Uninitialized)
+                .expect("Could not grow to 9 bytes")
+        };
+        assert_eq!(memory.size, 12);
+        assert_eq!(region.capacity_left(), 20);
+
+        let layout = Layout::from_size_align(12, 4).expect("Invalid layout");
+        let memory = unsafe {
+            region
+                .grow_with_slack(memory.ptr, layout, 30, AllocInit::Uninitialized)
+                .expect("Could not grow to 30 bytes")
+        };
+        // The slack is limited by the remaining capacity
+        assert_eq!(memory.size, 32);
+        assert_eq!(region.capacity_left(), 0);
+
+        unsafe {
+            region
+                .grow_with_slack(
+                    memory.ptr,
+                    Layout::from_size_align_unchecked(32, 4),
+                    33,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 33 bytes");
+            region.dealloc(memory.ptr, Layout::from_size_align_unchecked(32, 4));
+        }
+        assert_eq!(region.capacity_left(), 32);
+        unsafe { Global.dealloc(buffer.ptr, buffer_layout) };
+    }
+
+    #[test]
+    fn grow_in_place_max() {
+        let mut data = [1; 32];
+        let mut region = Region::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        let first = region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        assert_eq!(region.grow_in_place_max(first.ptr, layout), 32);
+
+        let second = region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        assert_eq!(region.grow_in_place_max(first.ptr, layout), 8);
+        assert_eq!(region.grow_in_place_max(second.ptr, layout), 24);
+    }
+
+    #[test]
+    fn rewind() {
+        let mut data = [1; 32];
+        let mut region = Region::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        let outer = region.checkpoint();
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+
+        let inner = region.checkpoint();
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        assert_eq!(region.capacity_left(), 8);
+
+        unsafe { region.rewind(inner) };
+        assert_eq!(region.capacity_left(), 24);
+
+        unsafe { region.rewind(outer) };
+        assert_eq!(region.capacity_left(), 32);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "reverse order")]
+    fn rewind_out_of_order() {
+        let mut data = [1; 32];
+        let mut region = Region::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        let outer = region.checkpoint();
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let inner = region.checkpoint();
+
+        unsafe {
+            region.rewind(outer);
+            region.rewind(inner);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "reverse order")]
+    fn rewind_out_of_order_after_alloc() {
+        let mut data = [1; 32];
+        let mut region = Region::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        let outer = region.checkpoint();
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let inner = region.checkpoint();
+
+        unsafe { region.rewind(outer) };
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        unsafe { region.rewind(inner) };
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "reverse order")]
+    fn rewind_stale() {
+        let mut data = [1; 32];
+        let mut region = Region::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        let outer = region.checkpoint();
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let inner = region.checkpoint();
+
+        unsafe { region.rewind(outer) };
+        let _first = region.checkpoint();
+        let _second = region.checkpoint();
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        unsafe { region.rewind(inner) };
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "reverse order")]
+    fn rewind_after_reset() {
+        let mut data = [1; 32];
+        let mut region = Region::new(&mut data);
+
+        let checkpoint = region.checkpoint();
+        region.reset();
+        let _new = region.checkpoint();
+        unsafe { region.rewind(checkpoint) };
+    }
 }
@@ -0,0 +1,449 @@
+use crate::Owns;
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    fmt,
+    ptr,
+    ptr::NonNull,
+};
+
+/// Allocator over an user-defined region of memory, which bumps downwards.
+///
+/// In contrast to [`Region`], `ReverseRegion` starts allocating at the end of the buffer and moves
+/// towards the start. Aligning the bump pointer is then a simple mask operation instead of
+/// computing the offset to the next aligned address. The `region` benchmark compares both
+/// directions.
+///
+/// As the end of the latest block is fixed by its neighbor, the latest block can't grow in place.
+/// Deallocating the latest block also releases the padding, which was needed to align it.
+/// Growing or shrinking it with [`ReallocPlacement::MayMove`] moves the block inside of the
+/// region without requesting new memory.
+///
+/// [`Region`]: crate::Region
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{Owns, ReverseRegion};
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+///
+/// let mut data = [0; 64];
+/// let mut region = ReverseRegion::new(&mut data);
+///
+/// let memory = region.alloc(Layout::new::<u32>(), AllocInit::Uninitialized)?;
+/// assert!(region.owns(memory));
+///
+/// unsafe { region.dealloc(memory.ptr, Layout::new::<u32>()) };
+/// assert!(!region.owns(memory));
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct ReverseRegion<'a> {
+    data: &'a mut [u8],
+    offset: usize,
+    /// The offset before the block at `offset` was allocated or `0`, if it's unknown, because the
+    /// successor of the block was deallocated.
+    latest_end: usize,
+}
+
+impl<'a> ReverseRegion<'a> {
+    #[inline]
+    pub fn new(data: &'a mut [u8]) -> Self {
+        let current = data.as_ptr() as usize + data.len();
+        Self {
+            data,
+            offset: current,
+            latest_end: 0,
+        }
+    }
+
+    /// Returns the total capacity available in this allocator.
+    pub const fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the free capacity left for allocating.
+    pub fn capacity_left(&self) -> usize {
+        self.offset - self.data.as_ptr() as usize
+    }
+
+    /// Resets the allocator.
+    ///
+    /// This implies, that `capacity()` and `capacity_left()` are equal afterwards.
+    pub fn reset(&mut self) {
+        self.offset = self.data.as_ptr() as usize + self.data.len();
+        self.latest_end = 0;
+        debug_assert_eq!(self.capacity(), self.capacity_left());
+    }
+
+    /// Checks if `memory` is the latest block, which was allocated.
+    /// For those blocks, it's possible to deallocate them or to move
+    /// them inside of the region when growing or shrinking.
+    #[inline]
+    pub fn is_last_block(&self, memory: MemoryBlock) -> bool {
+        memory.ptr.as_ptr() as usize == self.offset
+    }
+
+    /// Returns the start of a block of `size` bytes aligned to `align`, which ends at `end` at
+    /// the latest.
+    fn block_start(&self, end: usize, size: usize, align: usize) -> Result<usize, AllocErr> {
+        let start = end.checked_sub(size).ok_or(AllocErr)? & !(align - 1);
+        if start < self.data.as_ptr() as usize {
+            Err(AllocErr)
+        } else {
+            Ok(start)
+        }
+    }
+
+    /// Returns the end of the latest block including the padding to the previous block.
+    fn latest_end(&self, ptr: NonNull<u8>, size: usize) -> usize {
+        self.latest_end.max(ptr.as_ptr() as usize + size)
+    }
+}
+
+impl fmt::Debug for ReverseRegion<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReverseRegion")
+            .field("capacity", &self.capacity())
+            .field("capacity_left", &self.capacity_left())
+            .finish()
+    }
+}
+
+unsafe impl AllocRef for ReverseRegion<'_> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        let start = self.block_start(self.offset, layout.size(), layout.align())?;
+
+        self.latest_end = self.offset;
+        self.offset = start;
+        let memory = MemoryBlock {
+            ptr: unsafe { NonNull::new_unchecked(start as *mut u8) },
+            size: layout.size(),
+        };
+        unsafe { init.init(memory) };
+
+        Ok(memory)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        debug_assert!(
+            self.owns(MemoryBlock {
+                ptr,
+                size: layout.size()
+            }),
+            "`ptr` must denote a block of memory currently allocated via this allocator"
+        );
+        if self.is_last_block(MemoryBlock {
+            ptr,
+            size: layout.size(),
+        }) {
+            // Restore the padding, so the previous block can be deallocated as well
+            self.offset = self.latest_end(ptr, layout.size());
+            self.latest_end = 0;
+        }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let size = layout.size();
+        debug_assert!(
+            new_size >= size,
+            "`new_size` must be greater than or equal to `layout.size()`"
+        );
+
+        if layout.size() == new_size {
+            return Ok(MemoryBlock {
+                ptr,
+                size: new_size,
+            });
+        }
+        if placement == ReallocPlacement::InPlace {
+            return Err(AllocErr);
+        }
+
+        let new_memory = if self.is_last_block(MemoryBlock {
+            ptr,
+            size: layout.size(),
+        }) {
+            let end = self.latest_end(ptr, size);
+            let start = self.block_start(end, new_size, layout.align())?;
+            self.latest_end = end;
+            self.offset = start;
+            let new_memory = MemoryBlock {
+                ptr: NonNull::new_unchecked(start as *mut u8),
+                size: new_size,
+            };
+            ptr::copy(ptr.as_ptr(), new_memory.ptr.as_ptr(), size);
+            new_memory
+        } else {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_memory = self.alloc(new_layout, AllocInit::Uninitialized)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), size);
+            new_memory
+        };
+        init.init_offset(new_memory, size);
+        Ok(new_memory)
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let size = layout.size();
+        debug_assert!(
+            new_size <= size,
+            "`new_size` must be smaller than or equal to `layout.size()`"
+        );
+
+        if placement == ReallocPlacement::MayMove
+            && self.is_last_block(MemoryBlock {
+                ptr,
+                size: layout.size(),
+            })
+        {
+            // Move the block to the end to return the freed memory to the region
+            let end = self.latest_end(ptr, size);
+            let start = self.block_start(end, new_size, layout.align())?;
+            self.latest_end = end;
+            self.offset = start;
+            let new_ptr = NonNull::new_unchecked(start as *mut u8);
+            ptr::copy(ptr.as_ptr(), new_ptr.as_ptr(), new_size);
+            Ok(MemoryBlock {
+                ptr: new_ptr,
+                size: new_size,
+            })
+        } else {
+            Ok(MemoryBlock {
+                ptr,
+                size: new_size,
+            })
+        }
+    }
+}
+
+impl Owns for ReverseRegion<'_> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.offset <= memory.ptr.as_ptr() as usize
+            && memory.ptr.as_ptr() as usize + memory.size
+                <= self.data.as_ptr() as usize + self.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::helper::AsSlice;
+    use std::alloc::Global;
+
+    #[test]
+    fn alloc() {
+        let mut data = [1; 32];
+        let mut region = ReverseRegion::new(&mut data);
+
+        assert_eq!(region.capacity(), 32);
+        assert_eq!(region.capacity(), region.capacity_left());
+
+        region
+            .alloc(Layout::new::<[u8; 16]>(), AllocInit::Zeroed)
+            .expect("Could not allocate 16 bytes");
+        assert_eq!(region.capacity_left(), 16);
+
+        region
+            .alloc(Layout::new::<[u8; 17]>(), AllocInit::Uninitialized)
+            .expect_err("Could allocate 17 bytes");
+
+        assert_eq!(&data[0..16], &[1; 16][..]);
+        assert_eq!(&data[16..], &[0; 16][..]);
+    }
+
+    #[test]
+    fn alloc_aligned() {
+        let memory = Global
+            .alloc(
+                Layout::from_size_align(1024, 64).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect("Could not allocate 1024 Bytes");
+        let data = unsafe { memory.as_slice_mut() };
+        let mut region = ReverseRegion::new(data);
+
+        region
+            .alloc(
+                Layout::from_size_align(5, 1).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect("Could not allocate 5 Bytes");
+
+        let aligned = region
+            .alloc(
+                Layout::from_size_align(16, 16).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect("Could not allocate 16 Bytes");
+        assert_eq!(aligned.ptr.as_ptr() as usize % 16, 0);
+        assert_eq!(region.capacity_left(), 1024 - 32);
+
+        unsafe {
+            Global.dealloc(
+                memory.ptr,
+                Layout::from_size_align(1024, 64).expect("Invalid layout"),
+            )
+        };
+    }
+
+    #[test]
+    fn dealloc() {
+        let mut data = [1; 32];
+        let mut region = ReverseRegion::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        let first = region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let second = region
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        assert!(region.is_last_block(second));
+        assert!(!region.is_last_block(first));
+
+        unsafe { region.dealloc(first.ptr, layout) };
+        // It is not possible to deallocate memory that was not allocated last.
+        assert!(region.owns(first));
+        assert_eq!(region.capacity_left(), 16);
+
+        unsafe {
+            region.dealloc(second.ptr, layout);
+            region.dealloc(first.ptr, layout);
+        }
+        assert_eq!(region.capacity_left(), 32);
+        assert!(!region.owns(first));
+    }
+
+    #[test]
+    fn dealloc_aligned() {
+        let buffer_layout = Layout::from_size_align(64, 64).expect("Invalid layout");
+        let buffer = Global
+            .alloc(buffer_layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 64 Bytes");
+        let data = unsafe { buffer.as_slice_mut() };
+        let mut region = ReverseRegion::new(data);
+        let unaligned = Layout::from_size_align(5, 1).expect("Invalid layout");
+        let aligned = Layout::from_size_align(16, 16).expect("Invalid layout");
+
+        let first = region
+            .alloc(unaligned, AllocInit::Uninitialized)
+            .expect("Could not allocate 5 Bytes");
+        let second = region
+            .alloc(aligned, AllocInit::Uninitialized)
+            .expect("Could not allocate 16 Bytes");
+        // 11 bytes of padding are needed to align the second block
+        assert_eq!(region.capacity_left(), 32);
+
+        unsafe {
+            region.dealloc(second.ptr, aligned);
+            assert_eq!(region.capacity_left(), 59);
+            assert!(region.is_last_block(first));
+
+            region.dealloc(first.ptr, unaligned);
+            assert_eq!(region.capacity_left(), 64);
+
+            Global.dealloc(buffer.ptr, buffer_layout);
+        }
+    }
+
+    #[test]
+    fn grow() {
+        let mut data = [1; 32];
+        let mut region = ReverseRegion::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        unsafe {
+            let memory = region
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.as_slice_mut().copy_from_slice(&[2; 8]);
+
+            region
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Zeroed,
+                )
+                .expect_err("Could grow to 16 bytes in place");
+
+            let memory = region
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert!(region.is_last_block(memory));
+            assert_eq!(region.capacity_left(), 16);
+            assert_eq!(&memory.as_slice()[..8], &[2; 8][..]);
+            assert_eq!(&memory.as_slice()[8..], &[0; 8][..]);
+
+            region
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    33,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 33 bytes");
+        }
+    }
+
+    #[test]
+    fn shrink() {
+        let mut data = [1; 32];
+        let mut region = ReverseRegion::new(&mut data);
+        let layout = Layout::new::<[u8; 16]>();
+
+        unsafe {
+            let memory = region
+                .alloc(layout, AllocInit::Zeroed)
+                .expect("Could not allocate 16 bytes");
+            memory.as_slice_mut()[..8].copy_from_slice(&[2; 8]);
+
+            let memory = region
+                .shrink(memory.ptr, layout, 12, ReallocPlacement::InPlace)
+                .expect("Could not shrink to 12 bytes");
+            assert_eq!(region.capacity_left(), 16);
+
+            let memory = region
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 12]>(),
+                    8,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 8 bytes");
+            assert!(region.is_last_block(memory));
+            // Moving the block reclaims the memory released by shrinking in place
+            assert_eq!(region.capacity_left(), 24);
+            assert_eq!(memory.as_slice(), &[2; 8][..]);
+
+            region.dealloc(memory.ptr, Layout::new::<[u8; 8]>());
+            assert_eq!(region.capacity_left(), 32);
+
+            region.reset();
+            assert_eq!(region.capacity_left(), 32);
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use crate::Owns;
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    fmt,
+    marker::PhantomData,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Allocator over an user-defined region of memory, which can be shared between threads.
+///
+/// `SharedRegion` behaves like [`Region`], but the current position is stored in an
+/// `AtomicUsize`. Thus, `AllocRef` is implemented for `&SharedRegion`, which makes it possible to
+/// allocate from multiple threads at the same time. Only the latest block can be deallocated,
+/// grown, or shrunk in place.
+///
+/// [`Region`]: crate::Region
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{Owns, SharedRegion};
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+///
+/// let mut data = [0; 64];
+/// let region = SharedRegion::new(&mut data);
+///
+/// let mut alloc = &region;
+/// let memory = alloc.alloc(Layout::new::<u32>(), AllocInit::Uninitialized)?;
+/// assert!(region.owns(memory));
+///
+/// unsafe { alloc.dealloc(memory.ptr, Layout::new::<u32>()) };
+/// assert!(!region.owns(memory));
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct SharedRegion<'a> {
+    data: NonNull<u8>,
+    len: usize,
+    offset: AtomicUsize,
+    _marker: PhantomData<&'a mut [u8]>,
+}
+
+// SAFETY: The buffer is borrowed mutably for `'a`, so it's only accessible through the region.
+// Every allocation reserves its range with an atomic compare-exchange on `offset`, thus
+// concurrent calls through `&SharedRegion` never hand out overlapping blocks.
+unsafe impl Send for SharedRegion<'_> {}
+unsafe impl Sync for SharedRegion<'_> {}
+
+impl<'a> SharedRegion<'a> {
+    #[inline]
+    pub fn new(data: &'a mut [u8]) -> Self {
+        let len = data.len();
+        let data = NonNull::from(data).cast::<u8>();
+        Self {
+            data,
+            len,
+            offset: AtomicUsize::new(data.as_ptr() as usize),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the total capacity available in this allocator.
+    pub const fn capacity(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the free capacity left for allocating.
+    pub fn capacity_left(&self) -> usize {
+        self.end() - self.offset.load(Ordering::Relaxed)
+    }
+
+    /// Resets the allocator.
+    ///
+    /// This implies, that `capacity()` and `capacity_left()` are equal afterwards.
+    pub fn reset(&mut self) {
+        *self.offset.get_mut() = self.data.as_ptr() as usize;
+        debug_assert_eq!(self.capacity(), self.capacity_left());
+    }
+
+    /// Checks if `memory` is the latest block, which was allocated.
+    /// For those blocks, it's possible to deallocate them or to grow
+    /// or shrink them in place.
+    ///
+    /// Note, that the result may be outdated as soon as another thread allocates memory.
+    #[inline]
+    pub fn is_last_block(&self, memory: MemoryBlock) -> bool {
+        memory.ptr.as_ptr() as usize + memory.size == self.offset.load(Ordering::Relaxed)
+    }
+
+    fn end(&self) -> usize {
+        self.data.as_ptr() as usize + self.len
+    }
+
+    /// Moves the end of the block starting at `ptr` from `old_size` to `new_size` if it is the
+    /// latest block.
+    fn resize_last_block(&self, ptr: NonNull<u8>, old_size: usize, new_size: usize) -> bool {
+        let start = ptr.as_ptr() as usize;
+        let new = match start.checked_add(new_size) {
+            Some(new) if new <= self.end() => new,
+            _ => return false,
+        };
+        self.offset
+            .compare_exchange(start + old_size, new, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    }
+}
+
+impl fmt::Debug for SharedRegion<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedRegion")
+            .field("capacity", &self.capacity())
+            .field("capacity_left", &self.capacity_left())
+            .finish()
+    }
+}
+
+unsafe impl AllocRef for &SharedRegion<'_> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        let mut current = self.offset.load(Ordering::Relaxed);
+        let start = loop {
+            let offset = (current as *mut u8).align_offset(layout.align());
+            let start = current.checked_add(offset).ok_or(AllocErr)?;
+            let new = start.checked_add(layout.size()).ok_or(AllocErr)?;
+            if new > self.end() {
+                return Err(AllocErr);
+            }
+
+            match self.offset.compare_exchange_weak(
+                current,
+                new,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break start,
+                Err(offset) => current = offset,
+            }
+        };
+
+        let memory = MemoryBlock {
+            ptr: unsafe { NonNull::new_unchecked(start as *mut u8) },
+            size: layout.size(),
+        };
+        unsafe { init.init(memory) };
+
+        Ok(memory)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        debug_assert!(
+            self.owns(MemoryBlock {
+                ptr,
+                size: layout.size()
+            }),
+            "`ptr` must denote a block of memory currently allocated via this allocator"
+        );
+        self.resize_last_block(ptr, layout.size(), 0);
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let size = layout.size();
+        debug_assert!(
+            new_size >= size,
+            "`new_size` must be greater than or equal to `layout.size()`"
+        );
+
+        if size == new_size || self.resize_last_block(ptr, size, new_size) {
+            let new_memory = MemoryBlock {
+                ptr,
+                size: new_size,
+            };
+            init.init_offset(new_memory, size);
+            Ok(new_memory)
+        } else if placement == ReallocPlacement::MayMove {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_memory = self.alloc(new_layout, init)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), size);
+            Ok(new_memory)
+        } else {
+            Err(AllocErr)
+        }
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let size = layout.size();
+        debug_assert!(
+            new_size <= size,
+            "`new_size` must be smaller than or equal to `layout.size()`"
+        );
+
+        if size == new_size || self.resize_last_block(ptr, size, new_size) {
+            Ok(MemoryBlock {
+                ptr,
+                size: new_size,
+            })
+        } else if placement == ReallocPlacement::MayMove {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_memory = self.alloc(new_layout, AllocInit::Uninitialized)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), new_size);
+            Ok(new_memory)
+        } else {
+            Err(AllocErr)
+        }
+    }
+}
+
+impl Owns for SharedRegion<'_> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.data.as_ptr() <= memory.ptr.as_ptr()
+            && memory.ptr.as_ptr() as usize + memory.size <= self.offset.load(Ordering::Relaxed)
+    }
+}
+
+impl Owns for &SharedRegion<'_> {
+    #[inline]
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        (**self).owns(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::helper::AsSlice;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn alloc() {
+        let mut data = [1; 32];
+        let region = SharedRegion::new(&mut data);
+        assert_eq!(region.capacity(), 32);
+        assert_eq!(region.capacity(), region.capacity_left());
+
+        let memory = (&region)
+            .alloc(Layout::new::<[u8; 16]>(), AllocInit::Zeroed)
+            .expect("Could not allocate 16 bytes");
+        assert_eq!(region.capacity_left(), 16);
+        assert!(region.owns(memory));
+        assert!(region.is_last_block(memory));
+
+        (&region)
+            .alloc(Layout::new::<[u8; 17]>(), AllocInit::Uninitialized)
+            .expect_err("Could allocate 17 bytes");
+
+        assert_eq!(&data[0..16], &[0; 16][..]);
+        assert_eq!(&data[16..], &[1; 16][..]);
+    }
+
+    #[test]
+    fn dealloc() {
+        let mut data = [1; 32];
+        let mut region = SharedRegion::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+        let mut alloc = &region;
+
+        let first = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let second = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+
+        unsafe { alloc.dealloc(first.ptr, layout) };
+        // It is not possible to deallocate memory that was not allocated last.
+        assert!(alloc.owns(first));
+        assert_eq!(alloc.capacity_left(), 16);
+
+        unsafe {
+            alloc.dealloc(second.ptr, layout);
+            alloc.dealloc(first.ptr, layout);
+        }
+        assert_eq!(alloc.capacity_left(), 32);
+
+        alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        region.reset();
+        assert_eq!(region.capacity_left(), region.capacity());
+    }
+
+    #[test]
+    fn realloc() {
+        let mut data = [1; 32];
+        let region = SharedRegion::new(&mut data);
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+        let mut alloc = &region;
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(&memory.as_slice()[8..], &[0; 8][..]);
+            assert_eq!(region.capacity_left(), 16);
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    8,
+                    ReallocPlacement::InPlace,
+                )
+                .expect("Could not shrink to 8 bytes");
+            assert_eq!(region.capacity_left(), 24);
+
+            alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 16 bytes in place");
+            alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(region.capacity_left(), 0);
+        }
+    }
+
+    fn shared_region(size: usize) -> Arc<SharedRegion<'static>> {
+        let data = Box::leak(vec![0; size].into_boxed_slice());
+        Arc::new(SharedRegion::new(data))
+    }
+
+    #[test]
+    fn concurrent_alloc() {
+        const THREADS: usize = 8;
+        const ALLOCS: usize = 1000;
+
+        let region = shared_region(THREADS * ALLOCS * 16);
+        let handles = (0..THREADS)
+            .map(|id| {
+                let region = Arc::clone(&region);
+                thread::spawn(move || {
+                    let mut alloc = &*region;
+                    (0..ALLOCS)
+                        .map(|_| {
+                            let memory = alloc
+                                .alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)
+                                .expect("Could not allocate 16 bytes");
+                            unsafe { memory.ptr.as_ptr().write_bytes(id as u8, memory.size) };
+                            memory.ptr.as_ptr() as usize
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut blocks = Vec::new();
+        for (id, handle) in handles.into_iter().enumerate() {
+            for ptr in handle.join().expect("Thread panicked") {
+                let memory = unsafe { std::slice::from_raw_parts(ptr as *const u8, 16) };
+                assert_eq!(memory, &[id as u8; 16][..]);
+                blocks.push(ptr);
+            }
+        }
+
+        blocks.sort_unstable();
+        assert!(blocks.windows(2).all(|w| w[0] + 16 <= w[1]));
+        assert_eq!(region.capacity_left(), 0);
+    }
+
+    #[test]
+    fn concurrent_realloc() {
+        const THREADS: usize = 8;
+        const ITERATIONS: usize = 1000;
+
+        let region = shared_region(THREADS * ITERATIONS * 32);
+        let handles = (0..THREADS)
+            .map(|id| {
+                let region = Arc::clone(&region);
+                thread::spawn(move || {
+                    let mut alloc = &*region;
+                    let layout = Layout::new::<[u8; 16]>();
+                    for _ in 0..ITERATIONS {
+                        unsafe {
+                            let memory = alloc
+                                .alloc(layout, AllocInit::Uninitialized)
+                                .expect("Could not allocate 16 bytes");
+                            memory.as_slice_mut().copy_from_slice(&[id as u8; 16]);
+
+                            match alloc.grow(
+                                memory.ptr,
+                                layout,
+                                32,
+                                ReallocPlacement::InPlace,
+                                AllocInit::Uninitialized,
+                            ) {
+                                Ok(memory) => {
+                                    assert_eq!(&memory.as_slice()[..16], &[id as u8; 16][..]);
+                                    alloc.dealloc(memory.ptr, Layout::new::<[u8; 32]>());
+                                }
+                                Err(_) => {
+                                    assert_eq!(memory.as_slice(), &[id as u8; 16][..]);
+                                    alloc.dealloc(memory.ptr, layout);
+                                }
+                            }
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().expect("Thread panicked");
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use crate::{Affix, Owns, Result};
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    mem,
+    ptr::{self, NonNull},
+};
+
+/// Header stored in front of every block allocated by [`SizeHeader`].
+///
+/// The alignment is chosen to be at least the alignment of the largest fundamental type on common
+/// platforms. Every allocation with an alignment less than or equal to the alignment of the header
+/// places the header directly in front of the returned pointer.
+#[repr(C, align(16))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Header {
+    size: usize,
+    align: usize,
+}
+
+/// An allocator, which stores the size and the alignment of every block in a prefix.
+///
+/// This makes it possible to deallocate or to reallocate memory without knowing the original
+/// [`Layout`], e.g. when pointers are passed through a C interface. Besides the [`AllocRef`]
+/// interface, `SizeHeader` offers a `malloc`-style facade with [`malloc`], [`calloc`],
+/// [`realloc`], and [`free`].
+///
+/// The alignment of the requested layouts is limited to [`MAX_ALIGN`], requests with a greater
+/// alignment fail.
+///
+/// [`malloc`]: Self::malloc
+/// [`calloc`]: Self::calloc
+/// [`realloc`]: Self::realloc
+/// [`free`]: Self::free
+/// [`MAX_ALIGN`]: Self::MAX_ALIGN
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::SizeHeader;
+/// use core::alloc::{AllocInit, AllocRef, Layout};
+/// use std::alloc::System;
+///
+/// let mut alloc = SizeHeader::new(System);
+///
+/// let memory = alloc.alloc(Layout::new::<[u32; 4]>(), AllocInit::Uninitialized)?;
+/// unsafe {
+///     assert_eq!(SizeHeader::<System>::usable_size(memory.ptr), 16);
+///     alloc.dealloc_unsized(memory.ptr);
+/// }
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+///
+/// Using the `malloc`-style facade:
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::SizeHeader;
+/// use std::alloc::System;
+///
+/// let mut alloc = SizeHeader::new(System);
+///
+/// unsafe {
+///     let ptr = alloc.calloc(4, 4);
+///     assert!(!ptr.is_null());
+///
+///     let ptr = alloc.realloc(ptr, 32);
+///     assert!(!ptr.is_null());
+///
+///     alloc.free(ptr);
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SizeHeader<A>(Affix<A, Header>);
+
+impl<A: Default> Default for SizeHeader<A> {
+    fn default() -> Self {
+        Self::new(A::default())
+    }
+}
+
+impl<A> SizeHeader<A> {
+    /// The greatest alignment supported by this allocator.
+    pub const MAX_ALIGN: usize = mem::align_of::<Header>();
+
+    pub const fn new(alloc: A) -> Self {
+        Self(Affix::new(alloc))
+    }
+
+    /// Returns a reference to the parent allocator.
+    pub const fn parent(&self) -> &A {
+        &self.0.alloc
+    }
+
+    /// Returns the header of the block at `ptr`.
+    ///
+    /// As the alignment of the block is at most the alignment of `Header`, the header is always
+    /// stored directly in front of `ptr`.
+    unsafe fn header(ptr: NonNull<u8>) -> NonNull<Header> {
+        let header = Affix::<A, Header>::prefix(ptr, Layout::new::<Header>());
+        debug_assert_eq!(
+            header.as_ptr() as usize,
+            ptr.as_ptr() as usize - mem::size_of::<Header>()
+        );
+        header
+    }
+
+    /// Returns the layout, the block at `ptr` was allocated with.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must denote a block of memory [*currently allocated*] via this allocator.
+    pub unsafe fn layout(ptr: NonNull<u8>) -> Layout {
+        let header = *Self::header(ptr).as_ptr();
+        Layout::from_size_align_unchecked(header.size, header.align)
+    }
+
+    /// Returns the number of bytes usable in the block at `ptr`.
+    ///
+    /// This may be greater than the size originally requested.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must denote a block of memory [*currently allocated*] via this allocator.
+    pub unsafe fn usable_size(ptr: NonNull<u8>) -> usize {
+        Self::layout(ptr).size()
+    }
+
+    unsafe fn write_header(memory: MemoryBlock, align: usize) {
+        Self::header(memory.ptr).as_ptr().write(Header {
+            size: memory.size,
+            align,
+        });
+    }
+}
+
+impl<A: AllocRef> SizeHeader<A> {
+    /// Deallocates the memory referenced by `ptr` without knowing its layout.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must denote a block of memory [*currently allocated*] via this allocator.
+    pub unsafe fn dealloc_unsized(&mut self, ptr: NonNull<u8>) {
+        self.dealloc(ptr, Self::layout(ptr))
+    }
+
+    /// Grows or shrinks the memory referenced by `ptr` to `new_size` bytes without knowing its
+    /// layout.
+    ///
+    /// The block may be moved. The content is preserved up to the smaller of the old and the new
+    /// size.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must denote a block of memory [*currently allocated*] via this allocator.
+    pub unsafe fn realloc_unsized(&mut self, ptr: NonNull<u8>, new_size: usize) -> Result {
+        let layout = Self::layout(ptr);
+        if new_size >= layout.size() {
+            self.grow(
+                ptr,
+                layout,
+                new_size,
+                ReallocPlacement::MayMove,
+                AllocInit::Uninitialized,
+            )
+        } else {
+            self.shrink(ptr, layout, new_size, ReallocPlacement::MayMove)
+        }
+    }
+
+    /// Allocates `size` bytes aligned to [`MAX_ALIGN`] and returns a pointer to it, or a null
+    /// pointer on failure.
+    ///
+    /// [`MAX_ALIGN`]: Self::MAX_ALIGN
+    pub fn malloc(&mut self, size: usize) -> *mut u8 {
+        self.malloc_with(size, AllocInit::Uninitialized)
+    }
+
+    /// Allocates zeroed memory for an array of `count` elements of `size` bytes each, or returns
+    /// a null pointer on failure or when the total size overflows.
+    pub fn calloc(&mut self, count: usize, size: usize) -> *mut u8 {
+        match count.checked_mul(size) {
+            Some(size) => self.malloc_with(size, AllocInit::Zeroed),
+            None => ptr::null_mut(),
+        }
+    }
+
+    /// Resizes the memory referenced by `ptr` to `size` bytes.
+    ///
+    /// A null `ptr` behaves like [`malloc`]. On failure, a null pointer is returned and `ptr` is
+    /// left untouched.
+    ///
+    /// [`malloc`]: Self::malloc
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or denote a block of memory [*currently allocated*] via this allocator.
+    pub unsafe fn realloc(&mut self, ptr: *mut u8, size: usize) -> *mut u8 {
+        match NonNull::new(ptr) {
+            Some(ptr) => self
+                .realloc_unsized(ptr, size)
+                .map_or(ptr::null_mut(), |memory| memory.ptr.as_ptr()),
+            None => self.malloc(size),
+        }
+    }
+
+    /// Deallocates the memory referenced by `ptr`. Passing a null pointer does nothing.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or denote a block of memory [*currently allocated*] via this allocator.
+    pub unsafe fn free(&mut self, ptr: *mut u8) {
+        if let Some(ptr) = NonNull::new(ptr) {
+            self.dealloc_unsized(ptr)
+        }
+    }
+
+    fn malloc_with(&mut self, size: usize, init: AllocInit) -> *mut u8 {
+        Layout::from_size_align(size, Self::MAX_ALIGN)
+            .map_err(|_| AllocErr)
+            .and_then(|layout| self.alloc(layout, init))
+            .map_or(ptr::null_mut(), |memory| memory.ptr.as_ptr())
+    }
+}
+
+unsafe impl<A: AllocRef> AllocRef for SizeHeader<A> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result {
+        if layout.align() > Self::MAX_ALIGN {
+            return Err(AllocErr);
+        }
+        let memory = self.0.alloc(layout, init)?;
+        unsafe { Self::write_header(memory, layout.align()) };
+        Ok(memory)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.dealloc(ptr, layout)
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result {
+        let memory = self.0.grow(ptr, layout, new_size, placement, init)?;
+        Self::write_header(memory, layout.align());
+        Ok(memory)
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result {
+        let memory = self.0.shrink(ptr, layout, new_size, placement)?;
+        Self::write_header(memory, layout.align());
+        Ok(memory)
+    }
+}
+
+impl<A: Owns> Owns for SizeHeader<A> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        self.0.owns(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::helper::{self, AsSlice};
+    use std::alloc::System;
+
+    #[test]
+    fn alloc() {
+        let mut alloc = SizeHeader::new(helper::tracker(System));
+
+        for &(size, align) in &[(0, 1), (1, 1), (8, 8), (13, 4), (32, 16)] {
+            let layout = Layout::from_size_align(size, align).expect("Invalid layout");
+            let memory = alloc
+                .alloc(layout, AllocInit::Zeroed)
+                .unwrap_or_else(|_| panic!("Could not allocate {} bytes", size));
+            assert_eq!(memory.ptr.as_ptr() as usize % align, 0);
+
+            unsafe {
+                assert_eq!(SizeHeader::<System>::usable_size(memory.ptr), memory.size);
+                assert_eq!(SizeHeader::<System>::layout(memory.ptr).align(), align);
+                assert_eq!(memory.as_slice(), &vec![0; memory.size][..]);
+                alloc.dealloc_unsized(memory.ptr);
+            }
+        }
+
+        alloc
+            .alloc(
+                Layout::from_size_align(8, 32).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect_err("Could allocate with an alignment of 32");
+    }
+
+    #[test]
+    fn realloc_unsized() {
+        let mut alloc = SizeHeader::new(helper::tracker(System));
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            memory.as_slice_mut().copy_from_slice(&[1; 8]);
+
+            let memory = alloc
+                .realloc_unsized(memory.ptr, 64)
+                .expect("Could not grow to 64 bytes");
+            assert_eq!(SizeHeader::<System>::usable_size(memory.ptr), 64);
+            assert_eq!(&memory.as_slice()[..8], &[1; 8][..]);
+
+            let memory = alloc
+                .realloc_unsized(memory.ptr, 4)
+                .expect("Could not shrink to 4 bytes");
+            assert_eq!(SizeHeader::<System>::usable_size(memory.ptr), 4);
+            assert_eq!(memory.as_slice(), &[1; 4][..]);
+
+            alloc.dealloc_unsized(memory.ptr);
+        }
+    }
+
+    #[test]
+    fn malloc() {
+        let mut alloc = SizeHeader::new(helper::tracker(System));
+
+        unsafe {
+            let ptr = alloc.malloc(8);
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % SizeHeader::<System>::MAX_ALIGN, 0);
+            ptr.write_bytes(1, 8);
+
+            let ptr = alloc.realloc(ptr, 16);
+            assert!(!ptr.is_null());
+            assert_eq!(*ptr.add(7), 1);
+            alloc.free(ptr);
+
+            let ptr = alloc.calloc(4, 8);
+            assert!(!ptr.is_null());
+            assert_eq!(
+                SizeHeader::<System>::usable_size(NonNull::new_unchecked(ptr)),
+                32
+            );
+            assert!((0..32).all(|i| *ptr.add(i) == 0));
+            alloc.free(ptr);
+
+            let ptr = alloc.realloc(ptr::null_mut(), 8);
+            assert!(!ptr.is_null());
+            alloc.free(ptr);
+
+            alloc.free(ptr::null_mut());
+            assert!(alloc.calloc(usize::MAX, 2).is_null());
+            assert!(alloc.malloc(usize::MAX).is_null());
+        }
+    }
+}
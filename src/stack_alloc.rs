@@ -0,0 +1,420 @@
+use crate::Owns;
+use core::{
+    alloc::{AllocErr, AllocInit, AllocRef, Layout, MemoryBlock, ReallocPlacement},
+    fmt,
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+};
+
+/// Allocator over an inline buffer of `N` bytes.
+///
+/// `StackAlloc` behaves like [`Region`], but it owns its memory instead of borrowing it, so no
+/// separate buffer is needed, e.g. for the primary allocator of a [`FallbackAlloc`] used for
+/// small-buffer optimizations.
+///
+/// As moving a `StackAlloc` also moves its memory, `AllocRef` is only implemented for
+/// `&mut StackAlloc`. The borrow ensures, that the allocator is not moved while blocks are in use.
+///
+/// [`Region`]: crate::Region
+/// [`FallbackAlloc`]: crate::FallbackAlloc
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{FallbackAlloc, Owns, StackAlloc};
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let mut stack = StackAlloc::<32>::new();
+/// let mut alloc = FallbackAlloc {
+///     primary: &mut stack,
+///     fallback: System,
+/// };
+///
+/// let small_memory = alloc.alloc(Layout::new::<u32>(), AllocInit::Uninitialized)?;
+/// let big_memory = alloc.alloc(Layout::new::<[u32; 64]>(), AllocInit::Uninitialized)?;
+///
+/// assert!(alloc.primary.owns(small_memory));
+/// assert!(!alloc.primary.owns(big_memory));
+///
+/// unsafe {
+///     alloc.dealloc(small_memory.ptr, Layout::new::<u32>());
+///     alloc.dealloc(big_memory.ptr, Layout::new::<[u32; 64]>());
+/// };
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct StackAlloc<const N: usize> {
+    data: [MaybeUninit<u8>; N],
+    offset: usize,
+}
+
+impl<const N: usize> StackAlloc<N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            data: [MaybeUninit::uninit(); N],
+            offset: 0,
+        }
+    }
+
+    /// Returns the total capacity available in this allocator.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the free capacity left for allocating.
+    pub const fn capacity_left(&self) -> usize {
+        N - self.offset
+    }
+
+    /// Resets the allocator.
+    ///
+    /// This implies, that `capacity()` and `capacity_left()` are equal afterwards.
+    pub fn reset(&mut self) {
+        self.offset = 0;
+        debug_assert_eq!(self.capacity(), self.capacity_left());
+    }
+
+    /// Checks if `memory` is the latest block, which was allocated.
+    /// For those blocks, it's possible to deallocate them or to grow
+    /// or shrink them in place.
+    #[inline]
+    pub fn is_last_block(&self, memory: MemoryBlock) -> bool {
+        memory.ptr.as_ptr() as usize + memory.size == self.base() + self.offset
+    }
+
+    fn base(&self) -> usize {
+        self.data.as_ptr() as usize
+    }
+
+    fn block_offset(&self, ptr: NonNull<u8>) -> usize {
+        ptr.as_ptr() as usize - self.base()
+    }
+}
+
+impl<const N: usize> Default for StackAlloc<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for StackAlloc<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StackAlloc")
+            .field("capacity", &self.capacity())
+            .field("capacity_left", &self.capacity_left())
+            .finish()
+    }
+}
+
+unsafe impl<const N: usize> AllocRef for &mut StackAlloc<N> {
+    fn alloc(&mut self, layout: Layout, init: AllocInit) -> Result<MemoryBlock, AllocErr> {
+        let current = unsafe { self.data.as_mut_ptr().cast::<u8>().add(self.offset) };
+        let start = self
+            .offset
+            .checked_add(current.align_offset(layout.align()))
+            .ok_or(AllocErr)?;
+
+        let new = start.checked_add(layout.size()).ok_or(AllocErr)?;
+        if new > N {
+            return Err(AllocErr);
+        }
+
+        self.offset = new;
+        let memory = MemoryBlock {
+            ptr: unsafe { NonNull::new_unchecked(self.data.as_mut_ptr().cast::<u8>().add(start)) },
+            size: layout.size(),
+        };
+        unsafe { init.init(memory) };
+
+        Ok(memory)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        debug_assert!(
+            self.owns(MemoryBlock {
+                ptr,
+                size: layout.size()
+            }),
+            "`ptr` must denote a block of memory currently allocated via this allocator"
+        );
+        if self.is_last_block(MemoryBlock {
+            ptr,
+            size: layout.size(),
+        }) {
+            self.offset = self.block_offset(ptr);
+        }
+    }
+
+    unsafe fn grow(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+        init: AllocInit,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let size = layout.size();
+        debug_assert!(
+            new_size >= size,
+            "`new_size` must be greater than or equal to `layout.size()`"
+        );
+
+        if layout.size() == new_size {
+            Ok(MemoryBlock {
+                ptr,
+                size: new_size,
+            })
+        } else if self.is_last_block(MemoryBlock {
+            ptr,
+            size: layout.size(),
+        }) {
+            let new = self
+                .block_offset(ptr)
+                .checked_add(new_size)
+                .ok_or(AllocErr)?;
+            if new > N {
+                return Err(AllocErr);
+            }
+            self.offset = new;
+            let new_memory = MemoryBlock {
+                ptr,
+                size: new_size,
+            };
+            init.init_offset(new_memory, layout.size());
+            Ok(new_memory)
+        } else if placement == ReallocPlacement::MayMove {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_memory = self.alloc(new_layout, init)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), size);
+            Ok(new_memory)
+        } else {
+            Err(AllocErr)
+        }
+    }
+
+    unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        placement: ReallocPlacement,
+    ) -> Result<MemoryBlock, AllocErr> {
+        let size = layout.size();
+        debug_assert!(
+            new_size <= size,
+            "`new_size` must be smaller than or equal to `layout.size()`"
+        );
+
+        if layout.size() == new_size {
+            Ok(MemoryBlock {
+                ptr,
+                size: new_size,
+            })
+        } else if self.is_last_block(MemoryBlock {
+            ptr,
+            size: layout.size(),
+        }) {
+            self.offset = self.block_offset(ptr) + new_size;
+            Ok(MemoryBlock {
+                ptr,
+                size: new_size,
+            })
+        } else if placement == ReallocPlacement::MayMove {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_memory = self.alloc(new_layout, AllocInit::Uninitialized)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_memory.ptr.as_ptr(), new_size);
+            Ok(new_memory)
+        } else {
+            Err(AllocErr)
+        }
+    }
+}
+
+impl<const N: usize> Owns for StackAlloc<N> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        let ptr = memory.ptr.as_ptr() as usize;
+        self.base() <= ptr && ptr + memory.size <= self.base() + self.offset
+    }
+}
+
+impl<const N: usize> Owns for &mut StackAlloc<N> {
+    fn owns(&self, memory: MemoryBlock) -> bool {
+        (**self).owns(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::{
+        helper::{self, AsSlice},
+        FallbackAlloc,
+        OwnedRegion,
+    };
+    use std::alloc::System;
+
+    #[test]
+    fn alloc() {
+        let mut stack = StackAlloc::<32>::new();
+        let mut alloc = &mut stack;
+        assert_eq!(alloc.capacity(), 32);
+        assert_eq!(alloc.capacity(), alloc.capacity_left());
+
+        let memory = alloc
+            .alloc(Layout::new::<[u8; 16]>(), AllocInit::Zeroed)
+            .expect("Could not allocate 16 bytes");
+        assert_eq!(alloc.capacity_left(), 16);
+        assert!(alloc.owns(memory));
+        unsafe { assert_eq!(memory.as_slice(), &[0; 16][..]) };
+
+        alloc
+            .alloc(Layout::new::<[u8; 17]>(), AllocInit::Uninitialized)
+            .expect_err("Could allocate 17 bytes");
+
+        alloc.reset();
+        assert_eq!(alloc.capacity(), alloc.capacity_left());
+    }
+
+    #[test]
+    fn alloc_aligned() {
+        let mut stack = StackAlloc::<64>::new();
+        let mut alloc = &mut stack;
+        alloc
+            .alloc(Layout::new::<u8>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 1 byte");
+        let memory = alloc
+            .alloc(
+                Layout::from_size_align(8, 8).expect("Invalid layout"),
+                AllocInit::Uninitialized,
+            )
+            .expect("Could not allocate 8 bytes");
+        assert_eq!(memory.ptr.as_ptr() as usize % 8, 0);
+    }
+
+    #[test]
+    fn dealloc() {
+        let mut stack = StackAlloc::<32>::new();
+        let mut alloc = &mut stack;
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        let first = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        let second = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+
+        unsafe { alloc.dealloc(first.ptr, layout) };
+        // It is not possible to deallocate memory that was not allocated last.
+        assert!(alloc.owns(first));
+        assert_eq!(alloc.capacity_left(), 16);
+
+        unsafe {
+            alloc.dealloc(second.ptr, layout);
+            alloc.dealloc(first.ptr, layout);
+        }
+        assert_eq!(alloc.capacity_left(), 32);
+        assert!(!alloc.owns(first));
+    }
+
+    #[test]
+    fn realloc() {
+        let mut stack = StackAlloc::<32>::new();
+        let mut alloc = &mut stack;
+        let layout = Layout::from_size_align(8, 1).expect("Invalid layout");
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Zeroed,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(&memory.as_slice()[8..], &[0; 8][..]);
+            assert_eq!(alloc.capacity_left(), 16);
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    8,
+                    ReallocPlacement::InPlace,
+                )
+                .expect("Could not shrink to 8 bytes");
+            assert_eq!(alloc.capacity_left(), 24);
+
+            alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::InPlace,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 16 bytes in place");
+            alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(alloc.capacity_left(), 0);
+        }
+    }
+
+    #[test]
+    fn fallback() {
+        let mut stack = StackAlloc::<16>::new();
+        let mut alloc = FallbackAlloc {
+            primary: &mut stack,
+            fallback: helper::tracker(System),
+        };
+
+        let small = alloc
+            .alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+        let big = alloc
+            .alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 16 bytes");
+        assert!(alloc.primary.owns(small));
+        assert!(!alloc.primary.owns(big));
+
+        unsafe {
+            alloc.dealloc(big.ptr, Layout::new::<[u8; 16]>());
+            alloc.dealloc(small.ptr, Layout::new::<[u8; 16]>());
+        }
+        assert_eq!(stack.capacity_left(), 16);
+    }
+
+    #[test]
+    fn owned_region() {
+        let mut stack = StackAlloc::<64>::new();
+        let mut region =
+            OwnedRegion::new(&mut stack, Layout::new::<[u8; 32]>()).expect("Could not allocate");
+
+        let memory = region
+            .alloc(Layout::new::<[u8; 16]>(), AllocInit::Zeroed)
+            .expect("Could not allocate 16 bytes");
+        assert!(region.owns(memory));
+        assert!(region.parent().owns(memory));
+        unsafe { assert_eq!(memory.as_slice(), &[0; 16][..]) };
+
+        drop(region);
+        assert_eq!(stack.capacity_left(), 64);
+    }
+}
@@ -4,14 +4,71 @@
 //!
 //! [`Proxy`]: crate::Proxy
 
+#[cfg(any(doc, feature = "alloc"))]
+mod call_site_counter;
+mod histogram;
+#[cfg(any(doc, test, feature = "alloc"))]
+mod leak_tracker;
+#[cfg(any(doc, feature = "prometheus"))]
+mod prometheus;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(any(doc, test, feature = "alloc"))]
+mod validator;
+
+pub use self::histogram::{AtomicHistogram, Histogram, SizeClass};
+
+#[cfg(any(doc, feature = "prometheus"))]
+#[cfg_attr(doc, doc(cfg(feature = "prometheus")))]
+pub use self::prometheus::{Prometheus, PrometheusLabels, PrometheusMetrics};
+
+#[cfg(any(doc, feature = "alloc"))]
+#[cfg_attr(doc, doc(cfg(feature = "alloc")))]
+pub use self::call_site_counter::{CallSite, CallSiteCounter};
+
+// The tests of this crate use `LeakTracker` and `Validator` to check the allocators
+#[cfg(any(doc, test, feature = "alloc"))]
+#[cfg_attr(doc, doc(cfg(feature = "alloc")))]
+pub use self::{
+    leak_tracker::{Leak, LeakHandler, LeakTracker},
+    validator::{Validator, Violation, ViolationHandler, ViolationKind},
+};
+
+use self::histogram::{size_class, CLASS_COUNT};
 use crate::CallbackRef;
 use core::{
     alloc::{AllocErr, AllocInit, Layout, MemoryBlock, ReallocPlacement},
     cell::Cell,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Bound, RangeBounds, Sub, SubAssign},
+    panic::Location,
     ptr::NonNull,
     sync::atomic::{AtomicU64, Ordering::Relaxed},
 };
 
+/// A method of `AllocRef` or `Owns`, which invokes a callback.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Operation {
+    Alloc,
+    Dealloc,
+    Grow,
+    Shrink,
+    Owns,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Alloc => "alloc",
+            Self::Dealloc => "dealloc",
+            Self::Grow => "grow",
+            Self::Shrink => "shrink",
+            Self::Owns => "owns",
+        })
+    }
+}
+
 #[repr(usize)]
 #[derive(Copy, Clone, PartialEq)]
 enum Stat {
@@ -21,7 +78,7 @@ enum Stat {
     Shrinks = 3,
     Owns = 4,
 }
-const STAT_COUNT: usize = 4;
+const STAT_COUNT: usize = 5;
 
 /// A primitive counter for collectiong statistics.
 #[derive(Debug, Default, PartialEq, Eq)]
@@ -45,6 +102,15 @@ impl Counter {
     fn get(&self, stat: Stat) -> u64 {
         self.stats[stat as usize].get()
     }
+    fn load(&self, index: usize) -> u64 {
+        self.stats[index].get()
+    }
+    fn swap(&self, index: usize, value: u64) -> u64 {
+        self.stats[index].replace(value)
+    }
+    fn add(&self, index: usize, value: u64) {
+        self.stats[index].set(self.stats[index].get() + value)
+    }
 }
 
 /// An atomic counter for collectiong statistics which can be shared between threads.
@@ -78,9 +144,39 @@ impl AtomicCounter {
     fn get(&self, stat: Stat) -> u64 {
         self.stats[stat as usize].load(Relaxed)
     }
+    fn load(&self, index: usize) -> u64 {
+        self.stats[index].load(Relaxed)
+    }
+    fn swap(&self, index: usize, value: u64) -> u64 {
+        self.stats[index].swap(value, Relaxed)
+    }
+    fn add(&self, index: usize, value: u64) {
+        self.stats[index].fetch_add(value, Relaxed);
+    }
 }
 
-macro_rules! impl_callback_ref {
+/// A copy of the statistics of a [`Counter`] or an [`AtomicCounter`].
+///
+/// Snapshots can be subtracted from each other to get the statistics of a code region, or added
+/// to combine the statistics of multiple counters.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CounterSnapshot {
+    stats: [u64; STAT_COUNT],
+}
+
+impl CounterSnapshot {
+    fn get(&self, stat: Stat) -> u64 {
+        self.stats[stat as usize]
+    }
+    fn values(&self) -> impl Iterator<Item = &u64> {
+        self.stats.iter()
+    }
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut u64> {
+        self.stats.iter_mut()
+    }
+}
+
+macro_rules! impl_stats {
     ($tt:tt) => {
         impl $tt {
             /// Returns the number of `alloc` calls.
@@ -112,6 +208,12 @@ macro_rules! impl_callback_ref {
                 self.get(Stat::Owns)
             }
         }
+    };
+}
+
+macro_rules! impl_callback_ref {
+    ($tt:tt) => {
+        impl_stats!($tt);
 
         unsafe impl CallbackRef for $tt {
             #[inline]
@@ -120,12 +222,18 @@ macro_rules! impl_callback_ref {
                 _layout: Layout,
                 _init: AllocInit,
                 _result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
             ) {
                 self.increment_stat(Stat::Allocs, 1)
             }
 
             #[inline]
-            fn dealloc(&self, _ptr: NonNull<u8>, _layout: Layout) {
+            fn dealloc(
+                &self,
+                _ptr: NonNull<u8>,
+                _layout: Layout,
+                _location: &'static Location<'static>,
+            ) {
                 self.increment_stat(Stat::Deallocs, 1);
             }
 
@@ -137,6 +245,7 @@ macro_rules! impl_callback_ref {
                 _placement: ReallocPlacement,
                 _init: AllocInit,
                 _result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
             ) {
                 self.increment_stat(Stat::Grows, 1)
             }
@@ -149,6 +258,7 @@ macro_rules! impl_callback_ref {
                 _new_size: usize,
                 _placement: ReallocPlacement,
                 _result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
             ) {
                 self.increment_stat(Stat::Shrinks, 1)
             }
@@ -161,8 +271,414 @@ macro_rules! impl_callback_ref {
     };
 }
 
+macro_rules! impl_snapshot {
+    ($tt:tt, $snapshot:tt) => {
+        impl $tt {
+            /// Returns a copy of the current statistics.
+            pub fn snapshot(&self) -> $snapshot {
+                let mut snapshot = $snapshot::default();
+                for (index, stat) in snapshot.values_mut().enumerate() {
+                    *stat = self.load(index);
+                }
+                snapshot
+            }
+
+            /// Returns the current statistics and resets the counter.
+            ///
+            /// Each statistic is taken atomically, but not all statistics at once. Calls recorded
+            /// concurrently by another thread are either part of the returned snapshot or remain
+            /// in the counter.
+            pub fn take(&self) -> $snapshot {
+                let mut snapshot = $snapshot::default();
+                for (index, stat) in snapshot.values_mut().enumerate() {
+                    *stat = self.swap(index, 0);
+                }
+                snapshot
+            }
+
+            /// Resets all statistics to zero.
+            ///
+            /// Snapshots taken before must not be subtracted from snapshots taken afterwards.
+            pub fn reset(&self) {
+                self.take();
+            }
+
+            /// Adds the statistics of `snapshot` to the counter.
+            ///
+            /// This can be used to combine the statistics of multiple counters:
+            /// `total.merge(&counter.snapshot())`.
+            pub fn merge(&self, snapshot: &$snapshot) {
+                for (index, &stat) in snapshot.values().enumerate() {
+                    self.add(index, stat);
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_snapshot_ops {
+    ($tt:tt) => {
+        impl Add for $tt {
+            type Output = Self;
+
+            fn add(mut self, rhs: Self) -> Self {
+                self += rhs;
+                self
+            }
+        }
+
+        impl AddAssign for $tt {
+            fn add_assign(&mut self, rhs: Self) {
+                for (lhs, rhs) in self.values_mut().zip(rhs.values()) {
+                    *lhs += rhs;
+                }
+            }
+        }
+
+        /// Returns the statistics recorded between two snapshots.
+        ///
+        /// `rhs` must have been taken before `self` without resetting the counter in between. In
+        /// debug builds, this panics if a statistic of `rhs` is greater than in `self`, otherwise
+        /// the statistic is zero.
+        impl Sub for $tt {
+            type Output = Self;
+
+            fn sub(mut self, rhs: Self) -> Self {
+                self -= rhs;
+                self
+            }
+        }
+
+        impl SubAssign for $tt {
+            fn sub_assign(&mut self, rhs: Self) {
+                for (lhs, rhs) in self.values_mut().zip(rhs.values()) {
+                    let difference = lhs.checked_sub(*rhs);
+                    debug_assert!(
+                        difference.is_some(),
+                        "The subtracted snapshot must not be newer than `self`"
+                    );
+                    *lhs = difference.unwrap_or(0);
+                }
+            }
+        }
+
+        impl Sum for $tt {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::default(), Add::add)
+            }
+        }
+    };
+}
+
 impl_callback_ref!(Counter);
 impl_callback_ref!(AtomicCounter);
+impl_stats!(CounterSnapshot);
+impl_snapshot_ops!(CounterSnapshot);
+impl_snapshot!(Counter, CounterSnapshot);
+impl_snapshot!(AtomicCounter, CounterSnapshot);
+
+#[repr(usize)]
+#[derive(Copy, Clone, PartialEq)]
+enum ByteStat {
+    Requested = 0,
+    Allocated = 1,
+    Live = 2,
+    Peak = 3,
+    Moved = 4,
+}
+const BYTE_STAT_COUNT: usize = 5;
+
+/// A counter for collecting the amount of memory passed through an allocator.
+///
+/// In contrast to [`Counter`], which counts the calls, `ByteCounter` tracks the number of bytes.
+/// The sizes are taken from the `Layout`, the actual sizes of the returned [`MemoryBlock`]s are
+/// used for [`total_allocated`] to measure the memory wasted by rounding up requests.
+///
+/// [`live`] and [`peak`] are exact, as long as every block is passed to `grow`, `shrink`, and
+/// `dealloc` with the size it was requested with. Callers may also pass any size up to the size
+/// of the returned [`MemoryBlock`], which cannot be distinguished by the counter. In this case
+/// both are approximate and `live` is clamped at zero.
+///
+/// [`total_allocated`]: Self::total_allocated
+/// [`live`]: Self::live
+/// [`peak`]: Self::peak
+///
+/// # Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{stats::ByteCounter, CallbackRef, ChunkAlloc, Proxy};
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let counter = ByteCounter::default();
+/// let mut alloc = Proxy {
+///     alloc: ChunkAlloc::<_, 64>(System),
+///     callbacks: counter.by_ref(),
+/// };
+///
+/// let memory = alloc.alloc(Layout::new::<[u8; 100]>(), AllocInit::Uninitialized)?;
+/// assert_eq!(counter.total_requested(), 100);
+/// assert_eq!(counter.total_allocated(), 128);
+/// assert_eq!(counter.wasted(), 28);
+///
+/// unsafe { alloc.dealloc(memory.ptr, Layout::new::<[u8; 100]>()) };
+/// assert_eq!(counter.live(), 0);
+/// assert_eq!(counter.peak(), 100);
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ByteCounter {
+    stats: [Cell<u64>; BYTE_STAT_COUNT],
+}
+
+impl PartialEq<AtomicByteCounter> for ByteCounter {
+    fn eq(&self, other: &AtomicByteCounter) -> bool {
+        self.stats
+            .iter()
+            .zip(other.stats.iter())
+            .all(|(lhs, rhs)| lhs.get() == rhs.load(Relaxed))
+    }
+}
+
+impl ByteCounter {
+    fn increment_stat(&self, stat: ByteStat, additional: u64) {
+        self.stats[stat as usize].set(self.stats[stat as usize].get() + additional)
+    }
+    fn get(&self, stat: ByteStat) -> u64 {
+        self.stats[stat as usize].get()
+    }
+    fn load(&self, index: usize) -> u64 {
+        self.stats[index].get()
+    }
+    fn increment_live(&self, additional: u64) {
+        self.increment_stat(ByteStat::Live, additional);
+        let live = self.get(ByteStat::Live);
+        if live > self.get(ByteStat::Peak) {
+            self.stats[ByteStat::Peak as usize].set(live)
+        }
+    }
+    fn decrement_live(&self, size: u64) {
+        let live = &self.stats[ByteStat::Live as usize];
+        live.set(live.get().saturating_sub(size))
+    }
+}
+
+/// An atomic counter for collecting the amount of memory passed through an allocator, which can
+/// be shared between threads.
+///
+/// See [`ByteCounter`] for details.
+#[derive(Debug, Default)]
+pub struct AtomicByteCounter {
+    stats: [AtomicU64; BYTE_STAT_COUNT],
+}
+
+impl PartialEq for AtomicByteCounter {
+    fn eq(&self, other: &Self) -> bool {
+        self.stats
+            .iter()
+            .zip(other.stats.iter())
+            .all(|(lhs, rhs)| lhs.load(Relaxed) == rhs.load(Relaxed))
+    }
+}
+
+impl PartialEq<ByteCounter> for AtomicByteCounter {
+    fn eq(&self, other: &ByteCounter) -> bool {
+        self.stats
+            .iter()
+            .zip(other.stats.iter())
+            .all(|(lhs, rhs)| lhs.load(Relaxed) == rhs.get())
+    }
+}
+
+impl AtomicByteCounter {
+    fn increment_stat(&self, stat: ByteStat, additional: u64) {
+        self.stats[stat as usize].fetch_add(additional, Relaxed);
+    }
+    fn get(&self, stat: ByteStat) -> u64 {
+        self.stats[stat as usize].load(Relaxed)
+    }
+    fn load(&self, index: usize) -> u64 {
+        self.stats[index].load(Relaxed)
+    }
+    fn increment_live(&self, additional: u64) {
+        let live = self.stats[ByteStat::Live as usize].fetch_add(additional, Relaxed) + additional;
+        self.stats[ByteStat::Peak as usize].fetch_max(live, Relaxed);
+    }
+    fn decrement_live(&self, size: u64) {
+        let _ = self.stats[ByteStat::Live as usize]
+            .fetch_update(Relaxed, Relaxed, |live| Some(live.saturating_sub(size)));
+    }
+}
+
+/// A copy of the statistics of a [`ByteCounter`] or an [`AtomicByteCounter`].
+///
+/// In contrast to [`CounterSnapshot`], byte snapshots cannot be added or subtracted, as
+/// [`live`] and [`peak`] are not cumulative.
+///
+/// [`live`]: Self::live
+/// [`peak`]: Self::peak
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ByteCounterSnapshot {
+    stats: [u64; BYTE_STAT_COUNT],
+}
+
+impl ByteCounterSnapshot {
+    fn get(&self, stat: ByteStat) -> u64 {
+        self.stats[stat as usize]
+    }
+}
+
+macro_rules! impl_byte_stats {
+    ($tt:tt) => {
+        impl $tt {
+            /// Returns the total number of bytes requested by successful `alloc` calls and the
+            /// additional bytes requested by successful `grow` calls.
+            #[inline]
+            pub fn total_requested(&self) -> u64 {
+                self.get(ByteStat::Requested)
+            }
+
+            /// Returns the total number of bytes returned by successful `alloc` calls and the
+            /// additional bytes returned by successful `grow` calls.
+            ///
+            /// This may be greater than [`total_requested`], if the allocator rounds up requests.
+            /// When `shrink` returns more than `new_size` bytes, the excess is added as well.
+            ///
+            /// [`total_requested`]: Self::total_requested
+            #[inline]
+            pub fn total_allocated(&self) -> u64 {
+                self.get(ByteStat::Allocated)
+            }
+
+            /// Returns the number of bytes returned by `alloc`, `grow`, and `shrink` in excess of the
+            /// requested size.
+            #[inline]
+            pub fn wasted(&self) -> u64 {
+                self.total_allocated()
+                    .saturating_sub(self.total_requested())
+            }
+
+            /// Returns the number of bytes currently allocated.
+            ///
+            /// This is approximate, if blocks are resized or deallocated with a size other than the
+            /// requested one.
+            #[inline]
+            pub fn live(&self) -> u64 {
+                self.get(ByteStat::Live)
+            }
+
+            /// Returns the highest number of bytes allocated at the same time.
+            #[inline]
+            pub fn peak(&self) -> u64 {
+                self.get(ByteStat::Peak)
+            }
+
+            /// Returns the number of bytes copied by `grow` and `shrink`, when the block was moved.
+            #[inline]
+            pub fn moved(&self) -> u64 {
+                self.get(ByteStat::Moved)
+            }
+        }
+    };
+}
+
+macro_rules! impl_byte_callback_ref {
+    ($tt:tt) => {
+        impl $tt {
+            /// Returns a copy of the current statistics.
+            pub fn snapshot(&self) -> ByteCounterSnapshot {
+                let mut snapshot = ByteCounterSnapshot::default();
+                for (index, stat) in snapshot.stats.iter_mut().enumerate() {
+                    *stat = self.load(index);
+                }
+                snapshot
+            }
+        }
+
+        unsafe impl CallbackRef for $tt {
+            #[inline]
+            fn alloc(
+                &self,
+                layout: Layout,
+                _init: AllocInit,
+                result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
+            ) {
+                if let Ok(memory) = result {
+                    self.increment_stat(ByteStat::Requested, layout.size() as u64);
+                    self.increment_stat(ByteStat::Allocated, memory.size as u64);
+                    self.increment_live(layout.size() as u64);
+                }
+            }
+
+            #[inline]
+            fn dealloc(
+                &self,
+                _ptr: NonNull<u8>,
+                layout: Layout,
+                _location: &'static Location<'static>,
+            ) {
+                self.decrement_live(layout.size() as u64);
+            }
+
+            fn grow(
+                &self,
+                ptr: NonNull<u8>,
+                layout: Layout,
+                new_size: usize,
+                _placement: ReallocPlacement,
+                _init: AllocInit,
+                result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
+            ) {
+                if let Ok(memory) = result {
+                    let additional = new_size.saturating_sub(layout.size()) as u64;
+                    self.increment_stat(ByteStat::Requested, additional);
+                    self.increment_stat(
+                        ByteStat::Allocated,
+                        memory.size.saturating_sub(layout.size()) as u64,
+                    );
+                    self.increment_live(additional);
+                    if memory.ptr != ptr {
+                        self.increment_stat(ByteStat::Moved, layout.size() as u64);
+                    }
+                }
+            }
+
+            #[inline]
+            fn shrink(
+                &self,
+                ptr: NonNull<u8>,
+                layout: Layout,
+                new_size: usize,
+                _placement: ReallocPlacement,
+                result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
+            ) {
+                if let Ok(memory) = result {
+                    self.increment_stat(
+                        ByteStat::Allocated,
+                        memory.size.saturating_sub(new_size) as u64,
+                    );
+                    self.decrement_live(layout.size().saturating_sub(new_size) as u64);
+                    if memory.ptr != ptr {
+                        self.increment_stat(ByteStat::Moved, new_size as u64);
+                    }
+                }
+            }
+
+            #[inline]
+            fn owns(&self, _success: bool) {}
+        }
+    };
+}
+
+impl_byte_stats!(ByteCounter);
+impl_byte_stats!(AtomicByteCounter);
+impl_byte_stats!(ByteCounterSnapshot);
+impl_byte_callback_ref!(ByteCounter);
+impl_byte_callback_ref!(AtomicByteCounter);
 
 #[repr(usize)]
 #[derive(Copy, Clone, PartialEq)]
@@ -248,19 +764,328 @@ pub enum ResultFilter {
     Err,
 }
 
+/// The operation, `AllocInit`, `ReallocPlacement`, result, and name of a `FilteredStat`.
+///
+/// `FILTERED_STATS` is the only table of the filtered statistics, the exporters derive their
+/// labels and field names from it.
+type FilteredStatKey = (
+    FilteredStat,
+    Operation,
+    Option<AllocInit>,
+    Option<ReallocPlacement>,
+    bool,
+    &'static str,
+);
+
+const FILTERED_STATS: [FilteredStatKey; FILTERED_STAT_COUNT] = [
+    (
+        FilteredStat::AllocsUninitializedOk,
+        Operation::Alloc,
+        Some(AllocInit::Uninitialized),
+        None,
+        true,
+        "allocs_uninitialized_ok",
+    ),
+    (
+        FilteredStat::AllocsUninitializedErr,
+        Operation::Alloc,
+        Some(AllocInit::Uninitialized),
+        None,
+        false,
+        "allocs_uninitialized_err",
+    ),
+    (
+        FilteredStat::AllocsZeroedOk,
+        Operation::Alloc,
+        Some(AllocInit::Zeroed),
+        None,
+        true,
+        "allocs_zeroed_ok",
+    ),
+    (
+        FilteredStat::AllocsZeroedErr,
+        Operation::Alloc,
+        Some(AllocInit::Zeroed),
+        None,
+        false,
+        "allocs_zeroed_err",
+    ),
+    (
+        FilteredStat::Deallocs,
+        Operation::Dealloc,
+        None,
+        None,
+        true,
+        "deallocs",
+    ),
+    (
+        FilteredStat::GrowsMayMoveUninitializedOk,
+        Operation::Grow,
+        Some(AllocInit::Uninitialized),
+        Some(ReallocPlacement::MayMove),
+        true,
+        "grows_may_move_uninitialized_ok",
+    ),
+    (
+        FilteredStat::GrowsMayMoveUninitializedErr,
+        Operation::Grow,
+        Some(AllocInit::Uninitialized),
+        Some(ReallocPlacement::MayMove),
+        false,
+        "grows_may_move_uninitialized_err",
+    ),
+    (
+        FilteredStat::GrowsInPlaceUninitializedOk,
+        Operation::Grow,
+        Some(AllocInit::Uninitialized),
+        Some(ReallocPlacement::InPlace),
+        true,
+        "grows_in_place_uninitialized_ok",
+    ),
+    (
+        FilteredStat::GrowsInPlaceUninitializedErr,
+        Operation::Grow,
+        Some(AllocInit::Uninitialized),
+        Some(ReallocPlacement::InPlace),
+        false,
+        "grows_in_place_uninitialized_err",
+    ),
+    (
+        FilteredStat::GrowsMayMoveZeroedOk,
+        Operation::Grow,
+        Some(AllocInit::Zeroed),
+        Some(ReallocPlacement::MayMove),
+        true,
+        "grows_may_move_zeroed_ok",
+    ),
+    (
+        FilteredStat::GrowsMayMoveZeroedErr,
+        Operation::Grow,
+        Some(AllocInit::Zeroed),
+        Some(ReallocPlacement::MayMove),
+        false,
+        "grows_may_move_zeroed_err",
+    ),
+    (
+        FilteredStat::GrowsInPlaceZeroedOk,
+        Operation::Grow,
+        Some(AllocInit::Zeroed),
+        Some(ReallocPlacement::InPlace),
+        true,
+        "grows_in_place_zeroed_ok",
+    ),
+    (
+        FilteredStat::GrowsInPlaceZeroedErr,
+        Operation::Grow,
+        Some(AllocInit::Zeroed),
+        Some(ReallocPlacement::InPlace),
+        false,
+        "grows_in_place_zeroed_err",
+    ),
+    (
+        FilteredStat::ShrinksMayMoveOk,
+        Operation::Shrink,
+        None,
+        Some(ReallocPlacement::MayMove),
+        true,
+        "shrinks_may_move_ok",
+    ),
+    (
+        FilteredStat::ShrinksMayMoveErr,
+        Operation::Shrink,
+        None,
+        Some(ReallocPlacement::MayMove),
+        false,
+        "shrinks_may_move_err",
+    ),
+    (
+        FilteredStat::ShrinksInPlaceOk,
+        Operation::Shrink,
+        None,
+        Some(ReallocPlacement::InPlace),
+        true,
+        "shrinks_in_place_ok",
+    ),
+    (
+        FilteredStat::ShrinksInPlaceErr,
+        Operation::Shrink,
+        None,
+        Some(ReallocPlacement::InPlace),
+        false,
+        "shrinks_in_place_err",
+    ),
+    (
+        FilteredStat::OwnsTrue,
+        Operation::Owns,
+        None,
+        None,
+        true,
+        "owns_true",
+    ),
+    (
+        FilteredStat::OwnsFalse,
+        Operation::Owns,
+        None,
+        None,
+        false,
+        "owns_false",
+    ),
+];
+
+/// Selects the calls counted by a [`FilteredCounter`] or a [`FilteredAtomicCounter`].
+///
+/// A new filter matches every call, each method narrows it down. Calls without the filtered
+/// attribute never match: `dealloc`, `shrink`, and `owns` are excluded when filtering by
+/// `AllocInit`, `alloc`, `dealloc`, and `owns` when filtering by `ReallocPlacement`. `dealloc`
+/// can't fail and always matches [`ResultFilter::Ok`], `owns` matches [`ResultFilter::Ok`] if the
+/// block was owned.
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{
+///     stats::{FilteredCounter, Operation, ResultFilter, StatFilter},
+///     CallbackRef,
+///     Proxy,
+/// };
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let counter = FilteredCounter::default();
+/// let mut alloc = Proxy {
+///     alloc: System,
+///     callbacks: counter.by_ref(),
+/// };
+///
+/// for size in &[8, 16, 256] {
+///     let layout = Layout::from_size_align(*size, 8).expect("Invalid layout");
+///     let memory = alloc.alloc(layout, AllocInit::Zeroed)?;
+///     unsafe { alloc.dealloc(memory.ptr, layout) };
+/// }
+///
+/// let deallocs = StatFilter::new().operation(Operation::Dealloc);
+/// assert_eq!(counter.num_calls(deallocs), 3);
+///
+/// let zeroed = StatFilter::new()
+///     .init(AllocInit::Zeroed)
+///     .result(ResultFilter::Ok);
+/// assert_eq!(counter.num_calls(zeroed), 3);
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StatFilter {
+    operation: Option<Operation>,
+    init: AllocInitFilter,
+    placement: ReallocPlacementFilter,
+    result: ResultFilter,
+}
+
+impl StatFilter {
+    /// Creates a new filter, which matches every call.
+    pub const fn new() -> Self {
+        Self {
+            operation: None,
+            init: AllocInitFilter::None,
+            placement: ReallocPlacementFilter::None,
+            result: ResultFilter::None,
+        }
+    }
+
+    /// Only matches calls of `operation`.
+    pub const fn operation(mut self, operation: Operation) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Only matches calls with `init`.
+    pub fn init(mut self, init: impl Into<AllocInitFilter>) -> Self {
+        self.init = init.into();
+        self
+    }
+
+    /// Only matches calls with `placement`.
+    pub fn placement(mut self, placement: impl Into<ReallocPlacementFilter>) -> Self {
+        self.placement = placement.into();
+        self
+    }
+
+    /// Only matches calls with `result`.
+    pub const fn result(mut self, result: ResultFilter) -> Self {
+        self.result = result;
+        self
+    }
+
+    fn matches(&self, key: FilteredStatKey) -> bool {
+        let (_, operation, init, placement, success, _) = key;
+        let result_matches = match self.result {
+            ResultFilter::None => true,
+            ResultFilter::Ok => success,
+            ResultFilter::Err => !success,
+        };
+        (self.operation.is_none() || self.operation == Some(operation))
+            && (self.init == AllocInitFilter::None || self.init == AllocInitFilter::from(init))
+            && (self.placement == ReallocPlacementFilter::None
+                || self.placement == ReallocPlacementFilter::from(placement))
+            && result_matches
+    }
+}
+
+impl Default for StatFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts `sizes` into the first and the last size class it overlaps, or `None` if it's empty.
+///
+/// Returns `true` as third element if `sizes` contains every size.
+fn size_classes(sizes: impl RangeBounds<usize>) -> Option<(usize, usize, bool)> {
+    let min = match sizes.start_bound() {
+        Bound::Included(&min) => min,
+        Bound::Excluded(&min) => min.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let max = match sizes.end_bound() {
+        Bound::Included(&max) => max,
+        Bound::Excluded(&max) => max.checked_sub(1)?,
+        Bound::Unbounded => usize::MAX,
+    };
+    if min > max {
+        return None;
+    }
+    Some((
+        size_class(min),
+        size_class(max),
+        min == 0 && max == usize::MAX,
+    ))
+}
+
 /// A counter for collectiong and filtering statistics.
+///
+/// The calls can be queried with a [`StatFilter`]. To break the calls down by size, use
+/// [`SizedFilteredCounter`].
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct FilteredCounter {
     stats: [Cell<u64>; FILTERED_STAT_COUNT],
 }
 
 impl FilteredCounter {
-    fn increment_stat(&self, stat: FilteredStat, additional: u64) {
-        self.stats[stat as usize].set(self.stats[stat as usize].get() + additional)
+    fn increment_stat(&self, stat: FilteredStat, _size: usize) {
+        self.stats[stat as usize].set(self.stats[stat as usize].get() + 1)
     }
     fn get(&self, stat: FilteredStat) -> u64 {
         self.stats[stat as usize].get()
     }
+    fn load(&self, index: usize) -> u64 {
+        self.stats[index].get()
+    }
+    fn swap(&self, index: usize, value: u64) -> u64 {
+        self.stats[index].replace(value)
+    }
+    fn add(&self, index: usize, value: u64) {
+        self.stats[index].set(self.stats[index].get() + value)
+    }
 }
 
 impl PartialEq<FilteredAtomicCounter> for FilteredCounter {
@@ -273,18 +1098,30 @@ impl PartialEq<FilteredAtomicCounter> for FilteredCounter {
 }
 
 /// An atomic counter for collectiong and filtering statistics which can be shared between threads.
+///
+/// The calls can be queried with a [`StatFilter`]. To break the calls down by size, use
+/// [`SizedFilteredAtomicCounter`].
 #[derive(Debug, Default)]
 pub struct FilteredAtomicCounter {
     stats: [AtomicU64; FILTERED_STAT_COUNT],
 }
 
 impl FilteredAtomicCounter {
-    fn increment_stat(&self, stat: FilteredStat, additional: u64) {
-        self.stats[stat as usize].fetch_add(additional, Relaxed);
+    fn increment_stat(&self, stat: FilteredStat, _size: usize) {
+        self.stats[stat as usize].fetch_add(1, Relaxed);
     }
     fn get(&self, stat: FilteredStat) -> u64 {
         self.stats[stat as usize].load(Relaxed)
     }
+    fn load(&self, index: usize) -> u64 {
+        self.stats[index].load(Relaxed)
+    }
+    fn swap(&self, index: usize, value: u64) -> u64 {
+        self.stats[index].swap(value, Relaxed)
+    }
+    fn add(&self, index: usize, value: u64) {
+        self.stats[index].fetch_add(value, Relaxed);
+    }
 }
 
 impl PartialEq for FilteredAtomicCounter {
@@ -305,13 +1142,199 @@ impl PartialEq<FilteredCounter> for FilteredAtomicCounter {
     }
 }
 
-macro_rules! impl_filtered_callback_ref {
+/// A copy of the statistics of a [`FilteredCounter`] or a [`FilteredAtomicCounter`].
+///
+/// Snapshots can be subtracted from each other to get the statistics of a code region, or added
+/// to combine the statistics of multiple counters.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FilteredCounterSnapshot {
+    stats: [u64; FILTERED_STAT_COUNT],
+}
+
+impl FilteredCounterSnapshot {
+    fn get(&self, stat: FilteredStat) -> u64 {
+        self.stats[stat as usize]
+    }
+    fn values(&self) -> impl Iterator<Item = &u64> {
+        self.stats.iter()
+    }
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut u64> {
+        self.stats.iter_mut()
+    }
+}
+
+/// A [`FilteredCounter`], which additionally breaks down the calls by size.
+///
+/// `alloc` and `dealloc` record the size of the layout, `grow` and `shrink` the new size. The
+/// sizes are sorted into the same power-of-two classes as in [`Histogram`], e.g. `9..=16`, so the
+/// counter stores one statistic per class. Queries by size are answered in whole classes.
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{
+///     stats::{Operation, SizedFilteredCounter, StatFilter},
+///     CallbackRef,
+///     Proxy,
+/// };
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let counter = SizedFilteredCounter::default();
+/// let mut alloc = Proxy {
+///     alloc: System,
+///     callbacks: counter.by_ref(),
+/// };
+///
+/// for size in &[8, 16, 256] {
+///     let layout = Layout::from_size_align(*size, 8).expect("Invalid layout");
+///     let memory = alloc.alloc(layout, AllocInit::Zeroed)?;
+///     unsafe { alloc.dealloc(memory.ptr, layout) };
+/// }
+///
+/// let deallocs = StatFilter::new().operation(Operation::Dealloc);
+/// assert_eq!(counter.num_calls(deallocs), 3);
+/// assert_eq!(counter.num_calls_in_size_classes(deallocs, ..=16), 2);
+/// assert_eq!(counter.num_deallocs_in_size_classes(129..=256), 1);
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+///
+/// [`Histogram`]: crate::stats::Histogram
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SizedFilteredCounter {
+    stats: [[Cell<u64>; CLASS_COUNT]; FILTERED_STAT_COUNT],
+}
+
+impl SizedFilteredCounter {
+    fn increment_stat(&self, stat: FilteredStat, size: usize) {
+        let count = &self.stats[stat as usize][size_class(size)];
+        count.set(count.get() + 1)
+    }
+    fn get(&self, stat: FilteredStat) -> u64 {
+        self.stats[stat as usize].iter().map(Cell::get).sum()
+    }
+    fn get_class(&self, stat: FilteredStat, class: usize) -> u64 {
+        self.stats[stat as usize][class].get()
+    }
+    fn stat(&self, index: usize) -> &Cell<u64> {
+        &self.stats[index / CLASS_COUNT][index % CLASS_COUNT]
+    }
+    fn load(&self, index: usize) -> u64 {
+        self.stat(index).get()
+    }
+    fn swap(&self, index: usize, value: u64) -> u64 {
+        self.stat(index).replace(value)
+    }
+    fn add(&self, index: usize, value: u64) {
+        self.stat(index).set(self.stat(index).get() + value)
+    }
+}
+
+impl PartialEq<SizedFilteredAtomicCounter> for SizedFilteredCounter {
+    fn eq(&self, other: &SizedFilteredAtomicCounter) -> bool {
+        (0..FILTERED_STAT_COUNT * CLASS_COUNT).all(|index| self.load(index) == other.load(index))
+    }
+}
+
+/// A [`FilteredAtomicCounter`], which additionally breaks down the calls by size.
+///
+/// See [`SizedFilteredCounter`] for details.
+#[derive(Debug, Default)]
+pub struct SizedFilteredAtomicCounter {
+    stats: [[AtomicU64; CLASS_COUNT]; FILTERED_STAT_COUNT],
+}
+
+impl SizedFilteredAtomicCounter {
+    fn increment_stat(&self, stat: FilteredStat, size: usize) {
+        self.stats[stat as usize][size_class(size)].fetch_add(1, Relaxed);
+    }
+    fn get(&self, stat: FilteredStat) -> u64 {
+        self.stats[stat as usize]
+            .iter()
+            .map(|count| count.load(Relaxed))
+            .sum()
+    }
+    fn get_class(&self, stat: FilteredStat, class: usize) -> u64 {
+        self.stats[stat as usize][class].load(Relaxed)
+    }
+    fn stat(&self, index: usize) -> &AtomicU64 {
+        &self.stats[index / CLASS_COUNT][index % CLASS_COUNT]
+    }
+    fn load(&self, index: usize) -> u64 {
+        self.stat(index).load(Relaxed)
+    }
+    fn swap(&self, index: usize, value: u64) -> u64 {
+        self.stat(index).swap(value, Relaxed)
+    }
+    fn add(&self, index: usize, value: u64) {
+        self.stat(index).fetch_add(value, Relaxed);
+    }
+}
+
+impl PartialEq for SizedFilteredAtomicCounter {
+    fn eq(&self, other: &Self) -> bool {
+        (0..FILTERED_STAT_COUNT * CLASS_COUNT).all(|index| self.load(index) == other.load(index))
+    }
+}
+
+impl PartialEq<SizedFilteredCounter> for SizedFilteredAtomicCounter {
+    fn eq(&self, other: &SizedFilteredCounter) -> bool {
+        (0..FILTERED_STAT_COUNT * CLASS_COUNT).all(|index| self.load(index) == other.load(index))
+    }
+}
+
+/// A copy of the statistics of a [`SizedFilteredCounter`] or a [`SizedFilteredAtomicCounter`].
+///
+/// Snapshots can be subtracted from each other to get the statistics of a code region, or added
+/// to combine the statistics of multiple counters. The breakdown by size can be dropped by
+/// converting it into a [`FilteredCounterSnapshot`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SizedFilteredCounterSnapshot {
+    stats: [[u64; CLASS_COUNT]; FILTERED_STAT_COUNT],
+}
+
+impl SizedFilteredCounterSnapshot {
+    fn get(&self, stat: FilteredStat) -> u64 {
+        self.stats[stat as usize].iter().sum()
+    }
+    fn get_class(&self, stat: FilteredStat, class: usize) -> u64 {
+        self.stats[stat as usize][class]
+    }
+    fn values(&self) -> impl Iterator<Item = &u64> {
+        self.stats.iter().flat_map(|classes| classes.iter())
+    }
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut u64> {
+        self.stats.iter_mut().flat_map(|classes| classes.iter_mut())
+    }
+}
+
+impl From<SizedFilteredCounterSnapshot> for FilteredCounterSnapshot {
+    fn from(snapshot: SizedFilteredCounterSnapshot) -> Self {
+        let mut stats = [0; FILTERED_STAT_COUNT];
+        for (stat, classes) in stats.iter_mut().zip(snapshot.stats.iter()) {
+            *stat = classes.iter().sum();
+        }
+        Self { stats }
+    }
+}
+
+macro_rules! impl_filtered_stats {
     ($tt:tt) => {
         impl $tt {
+            /// Returns the number of calls matching `filter`.
+            pub fn num_calls(&self, filter: StatFilter) -> u64 {
+                FILTERED_STATS
+                    .iter()
+                    .filter(|&&key| filter.matches(key))
+                    .map(|key| self.get(key.0))
+                    .sum()
+            }
+
             /// Returns the total number of `alloc` calls.
             #[inline]
             pub fn num_allocs(&self) -> u64 {
-                self.num_allocs_filter(AllocInitFilter::None, ResultFilter::None)
+                self.num_calls(StatFilter::new().operation(Operation::Alloc))
             }
 
             /// Returns the filtered number of `alloc` calls.
@@ -320,44 +1343,24 @@ macro_rules! impl_filtered_callback_ref {
                 init: impl Into<AllocInitFilter>,
                 result: ResultFilter,
             ) -> u64 {
-                match (init.into(), result) {
-                    (AllocInitFilter::Uninitialized, ResultFilter::Ok) => {
-                        self.get(FilteredStat::AllocsUninitializedOk)
-                    }
-                    (AllocInitFilter::Uninitialized, ResultFilter::Err) => {
-                        self.get(FilteredStat::AllocsUninitializedErr)
-                    }
-                    (AllocInitFilter::Zeroed, ResultFilter::Ok) => {
-                        self.get(FilteredStat::AllocsZeroedOk)
-                    }
-                    (AllocInitFilter::Zeroed, ResultFilter::Err) => {
-                        self.get(FilteredStat::AllocsZeroedErr)
-                    }
-                    (AllocInitFilter::None, _) => {
-                        self.num_allocs_filter(AllocInitFilter::Uninitialized, result)
-                            + self.num_allocs_filter(AllocInitFilter::Zeroed, result)
-                    }
-                    (i, ResultFilter::None) => {
-                        self.num_allocs_filter(i, ResultFilter::Ok)
-                            + self.num_allocs_filter(i, ResultFilter::Err)
-                    }
-                }
+                self.num_calls(
+                    StatFilter::new()
+                        .operation(Operation::Alloc)
+                        .init(init)
+                        .result(result),
+                )
             }
 
             /// Returns the total number of `dealloc` calls.
             #[inline]
             pub fn num_deallocs(&self) -> u64 {
-                self.get(FilteredStat::Deallocs)
+                self.num_calls(StatFilter::new().operation(Operation::Dealloc))
             }
 
             /// Returns the total number of `grow` calls.
             #[inline]
             pub fn num_grows(&self) -> u64 {
-                self.num_grows_filter(
-                    ReallocPlacementFilter::None,
-                    AllocInitFilter::None,
-                    ResultFilter::None,
-                )
+                self.num_calls(StatFilter::new().operation(Operation::Grow))
             }
 
             /// Returns the filtered number of `grow` calls.
@@ -367,66 +1370,19 @@ macro_rules! impl_filtered_callback_ref {
                 init: impl Into<AllocInitFilter>,
                 result: ResultFilter,
             ) -> u64 {
-                match (placement.into(), init.into(), result) {
-                    (
-                        ReallocPlacementFilter::MayMove,
-                        AllocInitFilter::Uninitialized,
-                        ResultFilter::Ok,
-                    ) => self.get(FilteredStat::GrowsMayMoveUninitializedOk),
-                    (
-                        ReallocPlacementFilter::MayMove,
-                        AllocInitFilter::Uninitialized,
-                        ResultFilter::Err,
-                    ) => self.get(FilteredStat::GrowsMayMoveUninitializedErr),
-                    (
-                        ReallocPlacementFilter::MayMove,
-                        AllocInitFilter::Zeroed,
-                        ResultFilter::Ok,
-                    ) => self.get(FilteredStat::GrowsMayMoveZeroedOk),
-                    (
-                        ReallocPlacementFilter::MayMove,
-                        AllocInitFilter::Zeroed,
-                        ResultFilter::Err,
-                    ) => self.get(FilteredStat::GrowsMayMoveZeroedErr),
-                    (
-                        ReallocPlacementFilter::InPlace,
-                        AllocInitFilter::Uninitialized,
-                        ResultFilter::Ok,
-                    ) => self.get(FilteredStat::GrowsInPlaceUninitializedOk),
-                    (
-                        ReallocPlacementFilter::InPlace,
-                        AllocInitFilter::Uninitialized,
-                        ResultFilter::Err,
-                    ) => self.get(FilteredStat::GrowsInPlaceUninitializedErr),
-                    (
-                        ReallocPlacementFilter::InPlace,
-                        AllocInitFilter::Zeroed,
-                        ResultFilter::Ok,
-                    ) => self.get(FilteredStat::GrowsInPlaceZeroedOk),
-                    (
-                        ReallocPlacementFilter::InPlace,
-                        AllocInitFilter::Zeroed,
-                        ResultFilter::Err,
-                    ) => self.get(FilteredStat::GrowsInPlaceZeroedErr),
-                    (ReallocPlacementFilter::None, i, _) => {
-                        self.num_grows_filter(ReallocPlacementFilter::MayMove, i, result)
-                            + self.num_grows_filter(ReallocPlacementFilter::InPlace, i, result)
-                    }
-                    (p, AllocInitFilter::None, _) => {
-                        self.num_grows_filter(p, AllocInitFilter::Uninitialized, result)
-                            + self.num_grows_filter(p, AllocInitFilter::Zeroed, result)
-                    }
-                    (p, i, ResultFilter::None) => {
-                        self.num_grows_filter(p, i, ResultFilter::Ok)
-                            + self.num_grows_filter(p, i, ResultFilter::Err)
-                    }
-                }
+                self.num_calls(
+                    StatFilter::new()
+                        .operation(Operation::Grow)
+                        .placement(placement)
+                        .init(init)
+                        .result(result),
+                )
             }
 
             /// Returns the total number of `shrink` calls.
             #[inline]
             pub fn num_shrinks(&self) -> u64 {
-                self.num_shrinks_filter(ReallocPlacementFilter::None, ResultFilter::None)
+                self.num_calls(StatFilter::new().operation(Operation::Shrink))
             }
 
             /// Returns the filtered number of `shrink` calls.
@@ -435,108 +1391,154 @@ macro_rules! impl_filtered_callback_ref {
                 placement: impl Into<ReallocPlacementFilter>,
                 result: ResultFilter,
             ) -> u64 {
-                match (placement.into(), result) {
-                    (ReallocPlacementFilter::MayMove, ResultFilter::Ok) => {
-                        self.get(FilteredStat::ShrinksMayMoveOk)
-                    }
-                    (ReallocPlacementFilter::MayMove, ResultFilter::Err) => {
-                        self.get(FilteredStat::ShrinksMayMoveErr)
-                    }
-                    (ReallocPlacementFilter::InPlace, ResultFilter::Ok) => {
-                        self.get(FilteredStat::ShrinksInPlaceOk)
-                    }
-                    (ReallocPlacementFilter::InPlace, ResultFilter::Err) => {
-                        self.get(FilteredStat::ShrinksInPlaceErr)
-                    }
-                    (ReallocPlacementFilter::None, _) => {
-                        self.num_shrinks_filter(ReallocPlacementFilter::MayMove, result)
-                            + self.num_shrinks_filter(ReallocPlacementFilter::InPlace, result)
-                    }
-                    (p, ResultFilter::None) => {
-                        self.num_shrinks_filter(p, ResultFilter::Ok)
-                            + self.num_shrinks_filter(p, ResultFilter::Err)
-                    }
-                }
+                self.num_calls(
+                    StatFilter::new()
+                        .operation(Operation::Shrink)
+                        .placement(placement)
+                        .result(result),
+                )
             }
 
             /// Returns the total number of `owns` calls.
             #[inline]
             pub fn num_owns(&self) -> u64 {
-                self.num_owns_filter(true) + self.num_owns_filter(false)
+                self.num_calls(StatFilter::new().operation(Operation::Owns))
             }
 
             /// Returns the filtered number of `owns` calls.
             pub fn num_owns_filter(&self, success: bool) -> u64 {
-                if success {
-                    self.get(FilteredStat::OwnsTrue)
+                let result = if success {
+                    ResultFilter::Ok
                 } else {
-                    self.get(FilteredStat::OwnsFalse)
-                }
+                    ResultFilter::Err
+                };
+                self.num_calls(StatFilter::new().operation(Operation::Owns).result(result))
+            }
+        }
+    };
+}
+
+macro_rules! impl_sized_filtered_stats {
+    ($tt:tt) => {
+        impl_filtered_stats!($tt);
+
+        impl $tt {
+            /// Returns the number of calls matching `filter` with a size class overlapping
+            /// `sizes`.
+            ///
+            /// The range is rounded outwards to whole size classes, so `..=10` counts all calls
+            /// up to a size of 16. `owns` calls don't have a size and are only counted, if `sizes`
+            /// contains every size like `..`.
+            pub fn num_calls_in_size_classes(
+                &self,
+                filter: StatFilter,
+                sizes: impl RangeBounds<usize>,
+            ) -> u64 {
+                let (first, last, all_sizes) = match size_classes(sizes) {
+                    Some(classes) => classes,
+                    None => return 0,
+                };
+                FILTERED_STATS
+                    .iter()
+                    .filter(|&&key| filter.matches(key))
+                    .map(|&(stat, operation, ..)| {
+                        if all_sizes {
+                            self.get(stat)
+                        } else if operation == Operation::Owns {
+                            0
+                        } else {
+                            (first..=last).map(|class| self.get_class(stat, class)).sum()
+                        }
+                    })
+                    .sum()
+            }
+
+            /// Returns the number of `dealloc` calls with a size class overlapping `sizes`.
+            ///
+            /// See [`num_calls_in_size_classes`] for how `sizes` is rounded.
+            ///
+            /// [`num_calls_in_size_classes`]: Self::num_calls_in_size_classes
+            pub fn num_deallocs_in_size_classes(&self, sizes: impl RangeBounds<usize>) -> u64 {
+                self.num_calls_in_size_classes(
+                    StatFilter::new().operation(Operation::Dealloc),
+                    sizes,
+                )
             }
         }
+    };
+}
 
+macro_rules! impl_filtered_callback_ref {
+    ($tt:tt) => {
         unsafe impl CallbackRef for $tt {
             #[inline]
             fn alloc(
                 &self,
-                _layout: Layout,
+                layout: Layout,
                 init: AllocInit,
                 result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
             ) {
                 match (init, result.is_ok()) {
                     (AllocInit::Uninitialized, true) => {
-                        self.increment_stat(FilteredStat::AllocsUninitializedOk, 1)
+                        self.increment_stat(FilteredStat::AllocsUninitializedOk, layout.size())
                     }
                     (AllocInit::Uninitialized, false) => {
-                        self.increment_stat(FilteredStat::AllocsUninitializedErr, 1)
+                        self.increment_stat(FilteredStat::AllocsUninitializedErr, layout.size())
                     }
                     (AllocInit::Zeroed, true) => {
-                        self.increment_stat(FilteredStat::AllocsZeroedOk, 1)
+                        self.increment_stat(FilteredStat::AllocsZeroedOk, layout.size())
                     }
                     (AllocInit::Zeroed, false) => {
-                        self.increment_stat(FilteredStat::AllocsZeroedErr, 1)
+                        self.increment_stat(FilteredStat::AllocsZeroedErr, layout.size())
                     }
                 }
             }
 
             #[inline]
-            fn dealloc(&self, _ptr: NonNull<u8>, _layout: Layout) {
-                self.increment_stat(FilteredStat::Deallocs, 1);
+            fn dealloc(
+                &self,
+                _ptr: NonNull<u8>,
+                layout: Layout,
+                _location: &'static Location<'static>,
+            ) {
+                self.increment_stat(FilteredStat::Deallocs, layout.size());
             }
 
             fn grow(
                 &self,
                 _ptr: NonNull<u8>,
                 _layout: Layout,
-                _new_size: usize,
+                new_size: usize,
                 placement: ReallocPlacement,
                 init: AllocInit,
                 result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
             ) {
                 match (placement, init, result.is_ok()) {
                     (ReallocPlacement::MayMove, AllocInit::Uninitialized, true) => {
-                        self.increment_stat(FilteredStat::GrowsMayMoveUninitializedOk, 1)
+                        self.increment_stat(FilteredStat::GrowsMayMoveUninitializedOk, new_size)
                     }
                     (ReallocPlacement::MayMove, AllocInit::Uninitialized, false) => {
-                        self.increment_stat(FilteredStat::GrowsMayMoveUninitializedErr, 1)
+                        self.increment_stat(FilteredStat::GrowsMayMoveUninitializedErr, new_size)
                     }
                     (ReallocPlacement::MayMove, AllocInit::Zeroed, true) => {
-                        self.increment_stat(FilteredStat::GrowsMayMoveZeroedOk, 1)
+                        self.increment_stat(FilteredStat::GrowsMayMoveZeroedOk, new_size)
                     }
                     (ReallocPlacement::MayMove, AllocInit::Zeroed, false) => {
-                        self.increment_stat(FilteredStat::GrowsMayMoveZeroedErr, 1)
+                        self.increment_stat(FilteredStat::GrowsMayMoveZeroedErr, new_size)
                     }
                     (ReallocPlacement::InPlace, AllocInit::Uninitialized, true) => {
-                        self.increment_stat(FilteredStat::GrowsInPlaceUninitializedOk, 1)
+                        self.increment_stat(FilteredStat::GrowsInPlaceUninitializedOk, new_size)
                     }
                     (ReallocPlacement::InPlace, AllocInit::Uninitialized, false) => {
-                        self.increment_stat(FilteredStat::GrowsInPlaceUninitializedErr, 1)
+                        self.increment_stat(FilteredStat::GrowsInPlaceUninitializedErr, new_size)
                     }
                     (ReallocPlacement::InPlace, AllocInit::Zeroed, true) => {
-                        self.increment_stat(FilteredStat::GrowsInPlaceZeroedOk, 1)
+                        self.increment_stat(FilteredStat::GrowsInPlaceZeroedOk, new_size)
                     }
                     (ReallocPlacement::InPlace, AllocInit::Zeroed, false) => {
-                        self.increment_stat(FilteredStat::GrowsInPlaceZeroedErr, 1)
+                        self.increment_stat(FilteredStat::GrowsInPlaceZeroedErr, new_size)
                     }
                 }
             }
@@ -546,22 +1548,23 @@ macro_rules! impl_filtered_callback_ref {
                 &self,
                 _ptr: NonNull<u8>,
                 _layout: Layout,
-                _new_size: usize,
+                new_size: usize,
                 placement: ReallocPlacement,
                 result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
             ) {
                 match (placement, result.is_ok()) {
                     (ReallocPlacement::MayMove, true) => {
-                        self.increment_stat(FilteredStat::ShrinksMayMoveOk, 1)
+                        self.increment_stat(FilteredStat::ShrinksMayMoveOk, new_size)
                     }
                     (ReallocPlacement::MayMove, false) => {
-                        self.increment_stat(FilteredStat::ShrinksMayMoveErr, 1)
+                        self.increment_stat(FilteredStat::ShrinksMayMoveErr, new_size)
                     }
                     (ReallocPlacement::InPlace, true) => {
-                        self.increment_stat(FilteredStat::ShrinksInPlaceOk, 1)
+                        self.increment_stat(FilteredStat::ShrinksInPlaceOk, new_size)
                     }
                     (ReallocPlacement::InPlace, false) => {
-                        self.increment_stat(FilteredStat::ShrinksInPlaceErr, 1)
+                        self.increment_stat(FilteredStat::ShrinksInPlaceErr, new_size)
                     }
                 }
             }
@@ -569,13 +1572,410 @@ macro_rules! impl_filtered_callback_ref {
             #[inline]
             fn owns(&self, success: bool) {
                 if success {
-                    self.increment_stat(FilteredStat::OwnsTrue, 1)
+                    self.increment_stat(FilteredStat::OwnsTrue, 0)
                 } else {
-                    self.increment_stat(FilteredStat::OwnsFalse, 1)
+                    self.increment_stat(FilteredStat::OwnsFalse, 0)
                 }
             }
         }
     };
 }
+impl_filtered_stats!(FilteredCounter);
+impl_filtered_stats!(FilteredAtomicCounter);
+impl_filtered_stats!(FilteredCounterSnapshot);
 impl_filtered_callback_ref!(FilteredCounter);
 impl_filtered_callback_ref!(FilteredAtomicCounter);
+impl_snapshot_ops!(FilteredCounterSnapshot);
+impl_snapshot!(FilteredCounter, FilteredCounterSnapshot);
+impl_snapshot!(FilteredAtomicCounter, FilteredCounterSnapshot);
+impl_sized_filtered_stats!(SizedFilteredCounter);
+impl_sized_filtered_stats!(SizedFilteredAtomicCounter);
+impl_sized_filtered_stats!(SizedFilteredCounterSnapshot);
+impl_filtered_callback_ref!(SizedFilteredCounter);
+impl_filtered_callback_ref!(SizedFilteredAtomicCounter);
+impl_snapshot_ops!(SizedFilteredCounterSnapshot);
+impl_snapshot!(SizedFilteredCounter, SizedFilteredCounterSnapshot);
+impl_snapshot!(SizedFilteredAtomicCounter, SizedFilteredCounterSnapshot);
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::{helper, Proxy};
+    use core::alloc::AllocRef;
+    use std::alloc::System;
+
+    #[test]
+    fn filtered_stats() {
+        for (index, key) in FILTERED_STATS.iter().enumerate() {
+            assert_eq!(key.0 as usize, index);
+        }
+    }
+
+    #[test]
+    fn bytes() {
+        let counter = ByteCounter::default();
+        let mut alloc = Proxy {
+            alloc: helper::tracker(System),
+            callbacks: counter.by_ref(),
+        };
+
+        unsafe {
+            let first = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            let second = alloc
+                .alloc(Layout::new::<[u8; 16]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 16 bytes");
+            assert_eq!(counter.total_requested(), 24);
+            assert_eq!(counter.live(), 24);
+
+            alloc.dealloc(first.ptr, Layout::new::<[u8; 8]>());
+            assert_eq!(counter.live(), 16);
+
+            let second = alloc
+                .grow(
+                    second.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    64,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 64 bytes");
+            assert_eq!(counter.live(), 64);
+            assert_eq!(counter.peak(), 64);
+
+            let second = alloc
+                .shrink(
+                    second.ptr,
+                    Layout::new::<[u8; 64]>(),
+                    32,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 32 bytes");
+            assert_eq!(counter.live(), 32);
+            assert_eq!(counter.peak(), 64);
+
+            alloc.dealloc(second.ptr, Layout::new::<[u8; 32]>());
+        }
+        assert_eq!(counter.live(), 0);
+        assert_eq!(counter.total_requested(), 72);
+        assert_eq!(counter.total_allocated(), 72);
+    }
+
+    fn record_bytes(callbacks: &impl CallbackRef) {
+        let layout = Layout::new::<[u8; 8]>();
+        let memory = MemoryBlock {
+            ptr: NonNull::dangling(),
+            size: 16,
+        };
+        let moved = NonNull::new(8 as *mut u8).expect("Invalid pointer");
+
+        callbacks.alloc(
+            layout,
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        callbacks.alloc(
+            layout,
+            AllocInit::Uninitialized,
+            Err(AllocErr),
+            Location::caller(),
+        );
+        callbacks.grow(
+            memory.ptr,
+            layout,
+            16,
+            ReallocPlacement::MayMove,
+            AllocInit::Uninitialized,
+            Ok(MemoryBlock {
+                ptr: moved,
+                size: 16,
+            }),
+            Location::caller(),
+        );
+        callbacks.dealloc(moved, Layout::new::<[u8; 16]>(), Location::caller());
+    }
+
+    #[test]
+    fn bytes_atomic() {
+        let counter = ByteCounter::default();
+        let atomic_counter = AtomicByteCounter::default();
+        record_bytes(&counter);
+        record_bytes(&atomic_counter);
+
+        assert_eq!(counter, atomic_counter);
+        assert_eq!(atomic_counter.total_requested(), 16);
+        assert_eq!(atomic_counter.total_allocated(), 24);
+        assert_eq!(atomic_counter.wasted(), 8);
+        assert_eq!(atomic_counter.live(), 0);
+        assert_eq!(atomic_counter.peak(), 16);
+        assert_eq!(atomic_counter.moved(), 8);
+    }
+
+    fn record_shrink(callbacks: &impl CallbackRef) {
+        let memory = MemoryBlock {
+            ptr: NonNull::dangling(),
+            size: 16,
+        };
+        callbacks.alloc(
+            Layout::new::<[u8; 16]>(),
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        callbacks.shrink(
+            memory.ptr,
+            Layout::new::<[u8; 16]>(),
+            4,
+            ReallocPlacement::InPlace,
+            Ok(memory),
+            Location::caller(),
+        );
+        callbacks.dealloc(memory.ptr, Layout::new::<[u8; 16]>(), Location::caller());
+    }
+
+    #[test]
+    fn bytes_shrink() {
+        let counter = ByteCounter::default();
+        let atomic_counter = AtomicByteCounter::default();
+        record_shrink(&counter);
+        record_shrink(&atomic_counter);
+
+        assert_eq!(counter, atomic_counter);
+        assert_eq!(counter.total_requested(), 16);
+        assert_eq!(counter.total_allocated(), 28);
+        assert_eq!(counter.wasted(), 12);
+        // The block was deallocated with a larger layout than it was shrunk to, so `live` is
+        // approximate and clamped at zero
+        assert_eq!(counter.live(), 0);
+        assert_eq!(atomic_counter.live(), 0);
+    }
+
+    #[test]
+    fn bytes_live() {
+        let counter = ByteCounter::default();
+        let mut alloc = Proxy {
+            alloc: helper::tracker(System),
+            callbacks: counter.by_ref(),
+        };
+
+        unsafe {
+            let first = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            let second = alloc
+                .alloc(Layout::new::<[u8; 32]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 32 bytes");
+            assert_eq!(counter.live(), 40);
+
+            let first = alloc
+                .grow(
+                    first.ptr,
+                    Layout::new::<[u8; 8]>(),
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 16 bytes");
+            let second = alloc
+                .shrink(
+                    second.ptr,
+                    Layout::new::<[u8; 32]>(),
+                    8,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 8 bytes");
+            assert_eq!(counter.live(), 24);
+            assert_eq!(counter.peak(), 48);
+
+            alloc.dealloc(second.ptr, Layout::new::<[u8; 8]>());
+            assert_eq!(counter.live(), 16);
+            alloc.dealloc(first.ptr, Layout::new::<[u8; 16]>());
+        }
+        assert_eq!(counter.live(), 0);
+        assert_eq!(counter.peak(), 48);
+    }
+
+    #[test]
+    fn snapshot() {
+        let counter = Counter::default();
+        let atomic_counter = AtomicCounter::default();
+        record_bytes(&counter);
+        record_bytes(&atomic_counter);
+        counter.owns(true);
+        atomic_counter.owns(true);
+
+        let before = counter.snapshot();
+        assert_eq!(before, atomic_counter.snapshot());
+        assert_eq!(before.num_allocs(), 2);
+        assert_eq!(before.num_grows(), 1);
+        assert_eq!(before.num_deallocs(), 1);
+        assert_eq!(before.num_owns(), 1);
+
+        record_bytes(&counter);
+        let delta = counter.snapshot() - before;
+        assert_eq!(delta.num_allocs(), 2);
+        assert_eq!(delta.num_owns(), 0);
+        assert_eq!(delta + before, counter.snapshot());
+
+        let total = AtomicCounter::default();
+        total.merge(&counter.take());
+        total.merge(&atomic_counter.take());
+        assert_eq!(counter.snapshot(), CounterSnapshot::default());
+        assert_eq!(atomic_counter.num_allocs(), 0);
+        assert_eq!(total.num_allocs(), 6);
+        assert_eq!(total.num_owns(), 2);
+        assert_eq!(
+            vec![before, delta, before]
+                .into_iter()
+                .sum::<CounterSnapshot>(),
+            total.snapshot()
+        );
+
+        total.reset();
+        assert_eq!(total, counter);
+    }
+
+    #[test]
+    fn owns() {
+        let counter = Counter::default();
+        let atomic_counter = AtomicCounter::default();
+        for &success in &[true, false, true] {
+            counter.owns(success);
+            atomic_counter.owns(success);
+        }
+
+        assert_eq!(counter.num_owns(), 3);
+        assert_eq!(atomic_counter.num_owns(), 3);
+        assert_eq!(counter.num_allocs(), 0);
+        assert_eq!(counter.snapshot().num_owns(), 3);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "The subtracted snapshot must not be newer than `self`")]
+    fn snapshot_sub_newer() {
+        let counter = Counter::default();
+        let before = counter.snapshot();
+        counter.owns(true);
+        let _ = before - counter.snapshot();
+    }
+
+    #[test]
+    fn filtered_snapshot() {
+        let counter = FilteredCounter::default();
+        let atomic_counter = FilteredAtomicCounter::default();
+        record_bytes(&counter);
+        record_bytes(&atomic_counter);
+
+        let before = atomic_counter.snapshot();
+        assert_eq!(before, counter.snapshot());
+        assert_eq!(
+            before.num_allocs_filter(AllocInit::Uninitialized, ResultFilter::Err),
+            1
+        );
+
+        atomic_counter.owns(false);
+        let delta = atomic_counter.take() - before;
+        assert_eq!(delta.num_allocs(), 0);
+        assert_eq!(delta.num_owns_filter(false), 1);
+        assert_eq!(atomic_counter.num_owns(), 0);
+
+        counter.merge(&delta);
+        assert_eq!(counter.num_owns(), 1);
+        assert_eq!(
+            counter.snapshot().num_grows_filter(
+                ReallocPlacement::MayMove,
+                AllocInitFilter::None,
+                ResultFilter::Ok
+            ),
+            1
+        );
+        counter.reset();
+        assert_eq!(counter, atomic_counter);
+    }
+
+    #[test]
+    fn stat_filter() {
+        let counter = FilteredAtomicCounter::default();
+        let sized_counter = SizedFilteredAtomicCounter::default();
+        record_filtered(&counter);
+        record_filtered(&sized_counter);
+
+        assert_eq!(counter.num_calls(StatFilter::new()), 5);
+        assert_eq!(sized_counter.num_calls(StatFilter::new()), 5);
+        assert_eq!(
+            counter.num_calls(StatFilter::new().result(ResultFilter::Ok)),
+            4
+        );
+        assert_eq!(
+            counter.num_calls(StatFilter::new().placement(ReallocPlacement::InPlace)),
+            1
+        );
+        assert_eq!(
+            counter.num_calls(StatFilter::new().init(AllocInit::Uninitialized)),
+            1
+        );
+        assert_eq!(counter.num_deallocs(), 2);
+        assert_eq!(
+            counter.num_grows_filter(None, AllocInit::Uninitialized, ResultFilter::Err),
+            1
+        );
+        assert_eq!(counter.num_owns_filter(true), 1);
+        assert_eq!(counter.num_owns_filter(false), 0);
+        assert_eq!(
+            FilteredCounterSnapshot::from(sized_counter.snapshot()),
+            counter.snapshot()
+        );
+    }
+
+    #[test]
+    fn size_classes() {
+        let counter = SizedFilteredAtomicCounter::default();
+        record_filtered(&counter);
+        let all = StatFilter::new();
+
+        assert_eq!(counter.num_calls_in_size_classes(all, ..), 5);
+        assert_eq!(counter.num_calls_in_size_classes(all, 0..), 5);
+        assert_eq!(counter.num_calls_in_size_classes(all, 1..), 4);
+        assert_eq!(counter.num_calls_in_size_classes(all, 9..=64), 1);
+        assert_eq!(counter.num_calls_in_size_classes(all, 65..), 1);
+        assert_eq!(counter.num_calls_in_size_classes(all, ..0), 0);
+
+        assert_eq!(counter.num_deallocs_in_size_classes(..=8), 1);
+        assert_eq!(counter.num_deallocs_in_size_classes(8..16), 1);
+        assert_eq!(counter.num_deallocs_in_size_classes(33..), 1);
+        assert_eq!(
+            counter.snapshot().num_calls_in_size_classes(
+                StatFilter::new()
+                    .operation(Operation::Alloc)
+                    .init(AllocInit::Zeroed),
+                5..=8
+            ),
+            1
+        );
+    }
+
+    fn record_filtered(callbacks: &impl CallbackRef) {
+        let layout = Layout::new::<[u8; 8]>();
+        let memory = MemoryBlock {
+            ptr: NonNull::dangling(),
+            size: 8,
+        };
+        callbacks.alloc(layout, AllocInit::Zeroed, Ok(memory), Location::caller());
+        callbacks.grow(
+            memory.ptr,
+            layout,
+            100,
+            ReallocPlacement::InPlace,
+            AllocInit::Uninitialized,
+            Err(AllocErr),
+            Location::caller(),
+        );
+        callbacks.dealloc(memory.ptr, layout, Location::caller());
+        callbacks.dealloc(memory.ptr, Layout::new::<[u8; 64]>(), Location::caller());
+        callbacks.owns(true);
+    }
+}
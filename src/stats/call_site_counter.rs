@@ -0,0 +1,295 @@
+use crate::CallbackRef;
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{
+    alloc::{AllocErr, AllocInit, Layout, MemoryBlock, ReallocPlacement},
+    cell::RefCell,
+    panic::Location,
+    ptr::NonNull,
+};
+
+/// Statistics of a single call site collected by [`CallSiteCounter`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CallSite {
+    /// The source file of the call site.
+    pub file: &'static str,
+    /// The line of the call site.
+    pub line: u32,
+    /// The number of `alloc` calls.
+    pub num_allocs: u64,
+    /// The number of `dealloc` calls.
+    pub num_deallocs: u64,
+    /// The number of `grow` calls.
+    pub num_grows: u64,
+    /// The number of `shrink` calls.
+    pub num_shrinks: u64,
+    /// The number of bytes allocated by successful `alloc` and `grow` calls.
+    pub allocated_bytes: u64,
+    /// The number of bytes released by `dealloc` and successful `shrink` calls.
+    pub deallocated_bytes: u64,
+}
+
+impl CallSite {
+    const fn new(file: &'static str, line: u32) -> Self {
+        Self {
+            file,
+            line,
+            num_allocs: 0,
+            num_deallocs: 0,
+            num_grows: 0,
+            num_shrinks: 0,
+            allocated_bytes: 0,
+            deallocated_bytes: 0,
+        }
+    }
+}
+
+/// Collects statistics for every call site passed by [`Proxy`].
+///
+/// Calls are aggregated per file and line, the column is ignored. This helps finding the code
+/// paths responsible for allocation churn.
+///
+/// As the call sites are stored in a map, `CallSiteCounter` requires the **"alloc"-feature** and
+/// should not be used for the global allocator.
+///
+/// [`Proxy`]: crate::Proxy
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{stats::CallSiteCounter, CallbackRef, Proxy};
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let counter = CallSiteCounter::default();
+/// let mut alloc = Proxy {
+///     alloc: System,
+///     callbacks: counter.by_ref(),
+/// };
+///
+/// for _ in 0..4 {
+///     let memory = alloc.alloc(Layout::new::<u64>(), AllocInit::Uninitialized)?;
+///     unsafe { alloc.dealloc(memory.ptr, Layout::new::<u64>()) };
+/// }
+///
+/// let call_sites = counter.call_sites();
+/// assert_eq!(call_sites.len(), 2);
+/// assert_eq!(call_sites[0].num_allocs, 4);
+/// assert_eq!(call_sites[0].allocated_bytes, 32);
+/// assert_eq!(call_sites[1].num_deallocs, 4);
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct CallSiteCounter {
+    call_sites: RefCell<BTreeMap<&'static str, BTreeMap<u32, CallSite>>>,
+}
+
+impl CallSiteCounter {
+    /// Creates a new `CallSiteCounter` without any recorded call sites.
+    pub const fn new() -> Self {
+        Self {
+            call_sites: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the number of recorded call sites.
+    pub fn len(&self) -> usize {
+        self.call_sites.borrow().values().map(BTreeMap::len).sum()
+    }
+
+    /// Returns `true` if no call site was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.call_sites.borrow().is_empty()
+    }
+
+    /// Returns the statistics of all recorded call sites ordered by file and line.
+    pub fn call_sites(&self) -> Vec<CallSite> {
+        self.call_sites
+            .borrow()
+            .values()
+            .flat_map(BTreeMap::values)
+            .copied()
+            .collect()
+    }
+
+    /// Returns the statistics of the call site at `file` and `line`.
+    pub fn call_site(&self, file: &str, line: u32) -> Option<CallSite> {
+        self.call_sites.borrow().get(file)?.get(&line).copied()
+    }
+
+    /// Removes all recorded call sites.
+    pub fn clear(&self) {
+        self.call_sites.borrow_mut().clear()
+    }
+
+    fn update(&self, location: &'static Location<'static>, f: impl FnOnce(&mut CallSite)) {
+        let (file, line) = (location.file(), location.line());
+        f(self
+            .call_sites
+            .borrow_mut()
+            .entry(file)
+            .or_default()
+            .entry(line)
+            .or_insert_with(|| CallSite::new(file, line)))
+    }
+}
+
+unsafe impl CallbackRef for CallSiteCounter {
+    fn alloc(
+        &self,
+        layout: Layout,
+        _init: AllocInit,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        self.update(location, |call_site| {
+            call_site.num_allocs += 1;
+            if result.is_ok() {
+                call_site.allocated_bytes += layout.size() as u64;
+            }
+        })
+    }
+
+    fn dealloc(&self, _ptr: NonNull<u8>, layout: Layout, location: &'static Location<'static>) {
+        self.update(location, |call_site| {
+            call_site.num_deallocs += 1;
+            call_site.deallocated_bytes += layout.size() as u64;
+        })
+    }
+
+    fn grow(
+        &self,
+        _ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        _placement: ReallocPlacement,
+        _init: AllocInit,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        self.update(location, |call_site| {
+            call_site.num_grows += 1;
+            if result.is_ok() {
+                call_site.allocated_bytes += (new_size - layout.size()) as u64;
+            }
+        })
+    }
+
+    fn shrink(
+        &self,
+        _ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        _placement: ReallocPlacement,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        self.update(location, |call_site| {
+            call_site.num_shrinks += 1;
+            if result.is_ok() {
+                call_site.deallocated_bytes += (layout.size() - new_size) as u64;
+            }
+        })
+    }
+
+    fn owns(&self, _success: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::{helper, Proxy};
+    use core::alloc::AllocRef;
+    use std::alloc::System;
+
+    /// Allocates a block and returns it with the location, which is passed to the callbacks.
+    #[track_caller]
+    fn alloc_at_caller(
+        alloc: &mut impl AllocRef,
+        layout: Layout,
+    ) -> (MemoryBlock, &'static Location<'static>) {
+        let memory = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        (memory, Location::caller())
+    }
+
+    #[test]
+    fn call_sites() {
+        let counter = CallSiteCounter::new();
+        let mut alloc = Proxy {
+            alloc: helper::tracker(System),
+            callbacks: counter.by_ref(),
+        };
+        let layout = Layout::new::<[u8; 8]>();
+
+        let mut blocks = Vec::new();
+        let mut locations = Vec::new();
+        for _ in 0..3 {
+            let (memory, location) = alloc_at_caller(&mut alloc, layout);
+            blocks.push(memory);
+            locations.push(location);
+        }
+        let location = locations[0];
+        assert!(locations.iter().all(|&other| other == location));
+
+        unsafe {
+            let memory = alloc
+                .grow(
+                    blocks[0].ptr,
+                    layout,
+                    32,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 32 bytes");
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 32]>(),
+                    16,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 16 bytes");
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 16]>());
+            for memory in &blocks[1..] {
+                alloc.dealloc(memory.ptr, layout);
+            }
+        }
+
+        assert_eq!(counter.len(), 5);
+        let call_sites = counter.call_sites();
+        assert!(call_sites.iter().all(|call_site| call_site.file == file!()));
+        assert_eq!(
+            call_sites
+                .iter()
+                .map(|call_site| call_site.num_allocs)
+                .sum::<u64>(),
+            3
+        );
+        assert_eq!(
+            call_sites
+                .iter()
+                .map(|call_site| call_site.allocated_bytes)
+                .sum::<u64>(),
+            48
+        );
+        assert_eq!(
+            call_sites
+                .iter()
+                .map(|call_site| call_site.deallocated_bytes)
+                .sum::<u64>(),
+            48
+        );
+
+        let call_site = counter
+            .call_site(location.file(), location.line())
+            .expect("The call site of `alloc` was not recorded");
+        assert_eq!(call_site.num_allocs, 3);
+        assert_eq!(call_site.allocated_bytes, 24);
+
+        counter.clear();
+        assert!(counter.is_empty());
+    }
+}
@@ -0,0 +1,475 @@
+use super::Operation;
+use crate::CallbackRef;
+use core::{
+    alloc::{AllocErr, AllocInit, Layout, MemoryBlock, ReallocPlacement},
+    cell::Cell,
+    fmt,
+    mem,
+    panic::Location,
+    ptr::NonNull,
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
+
+pub(super) const CLASS_COUNT: usize = 32;
+const OPERATION_COUNT: usize = 4;
+
+/// Returns the index of the smallest power of two greater than or equal to `size`.
+pub(super) fn size_class(size: usize) -> usize {
+    if size <= 1 {
+        0
+    } else {
+        let bits = mem::size_of::<usize>() * 8 - (size - 1).leading_zeros() as usize;
+        bits.min(CLASS_COUNT - 1)
+    }
+}
+
+/// Returns the smallest and the largest size in `class`.
+fn size_class_bounds(class: usize) -> (usize, usize) {
+    match class {
+        0 => (0, 1),
+        _ if class == CLASS_COUNT - 1 => ((1 << (class - 1)) + 1, usize::MAX),
+        _ => ((1 << (class - 1)) + 1, 1 << class),
+    }
+}
+
+fn align_class(align: usize) -> usize {
+    (align.trailing_zeros() as usize).min(CLASS_COUNT - 1)
+}
+
+fn operation_index(operation: Operation) -> Option<usize> {
+    match operation {
+        Operation::Alloc => Some(0),
+        Operation::Dealloc => Some(1),
+        Operation::Grow => Some(2),
+        Operation::Shrink => Some(3),
+        Operation::Owns => None,
+    }
+}
+
+/// A class of sizes or alignments in a [`Histogram`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SizeClass {
+    /// The smallest value in this class.
+    pub min: usize,
+    /// The largest value in this class.
+    pub max: usize,
+    /// The number of recorded values in this class.
+    pub count: u64,
+}
+
+impl SizeClass {
+    fn size(class: usize, count: u64) -> Self {
+        let (min, max) = size_class_bounds(class);
+        Self { min, max, count }
+    }
+
+    fn align(class: usize, count: u64) -> Self {
+        let max = if class == CLASS_COUNT - 1 {
+            usize::MAX
+        } else {
+            1 << class
+        };
+        Self {
+            min: 1 << class,
+            max,
+            count,
+        }
+    }
+}
+
+impl fmt::Display for SizeClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else if self.max == usize::MAX {
+            write!(f, "{}..", self.min)
+        } else {
+            write!(f, "{}..={}", self.min, self.max)
+        }
+    }
+}
+
+/// Displays the upper bound of the last class as `..` while respecting the width.
+struct Max(usize);
+
+impl fmt::Display for Max {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == usize::MAX {
+            f.pad("..")
+        } else {
+            fmt::Display::fmt(&self.0, f)
+        }
+    }
+}
+
+/// A histogram for collecting the sizes and alignments passed to an allocator.
+///
+/// The sizes are sorted into power-of-two classes, so the first class contains the sizes `0` and
+/// `1`, followed by `2`, `3..=4`, `5..=8` and so on. The last class contains every size greater
+/// than 2<sup>30</sup>. `alloc` and `dealloc` record the size of the layout, `grow` and `shrink`
+/// record `new_size` of successful calls. Additionally, the alignment of every successful `alloc`
+/// call is recorded.
+///
+/// The classes can be iterated with [`sizes`] and [`alignments`], the sum of all recorded sizes
+/// is returned by [`total_size`]. The `Display` implementation renders all non-empty classes as a
+/// table.
+///
+/// [`sizes`]: Self::sizes
+/// [`total_size`]: Self::total_size
+/// [`alignments`]: Self::alignments
+///
+/// # Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{
+///     stats::{Histogram, Operation},
+///     CallbackRef,
+///     Proxy,
+/// };
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let histogram = Histogram::default();
+/// let mut alloc = Proxy {
+///     alloc: System,
+///     callbacks: histogram.by_ref(),
+/// };
+///
+/// for size in &[3, 4, 100] {
+///     let layout = Layout::from_size_align(*size, 1).unwrap();
+///     let memory = alloc.alloc(layout, AllocInit::Uninitialized)?;
+///     unsafe { alloc.dealloc(memory.ptr, layout) };
+/// }
+///
+/// let mut sizes = histogram.sizes(Operation::Alloc);
+/// let class = sizes.next().unwrap();
+/// assert_eq!((class.min, class.max, class.count), (3, 4, 2));
+/// let class = sizes.next().unwrap();
+/// assert_eq!((class.min, class.max, class.count), (65, 128, 1));
+/// assert_eq!(sizes.next(), None);
+///
+/// println!("{}", histogram);
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Histogram {
+    sizes: [[Cell<u64>; CLASS_COUNT]; OPERATION_COUNT],
+    totals: [Cell<u64>; OPERATION_COUNT],
+    alignments: [Cell<u64>; CLASS_COUNT],
+}
+
+impl Histogram {
+    fn increment_size(&self, operation: usize, size: usize) {
+        let count = &self.sizes[operation][size_class(size)];
+        count.set(count.get() + 1);
+        let total = &self.totals[operation];
+        total.set(total.get() + size as u64)
+    }
+    fn increment_align(&self, class: usize) {
+        let count = &self.alignments[class];
+        count.set(count.get() + 1)
+    }
+    fn get_size(&self, operation: usize, class: usize) -> u64 {
+        self.sizes[operation][class].get()
+    }
+    fn get_total(&self, operation: usize) -> u64 {
+        self.totals[operation].get()
+    }
+    fn get_align(&self, class: usize) -> u64 {
+        self.alignments[class].get()
+    }
+}
+
+/// An atomic histogram for collecting the sizes and alignments passed to an allocator, which can
+/// be shared between threads.
+///
+/// See [`Histogram`] for details.
+#[derive(Debug, Default)]
+pub struct AtomicHistogram {
+    sizes: [[AtomicU64; CLASS_COUNT]; OPERATION_COUNT],
+    totals: [AtomicU64; OPERATION_COUNT],
+    alignments: [AtomicU64; CLASS_COUNT],
+}
+
+impl AtomicHistogram {
+    fn increment_size(&self, operation: usize, size: usize) {
+        self.sizes[operation][size_class(size)].fetch_add(1, Relaxed);
+        self.totals[operation].fetch_add(size as u64, Relaxed);
+    }
+    fn increment_align(&self, class: usize) {
+        self.alignments[class].fetch_add(1, Relaxed);
+    }
+    fn get_size(&self, operation: usize, class: usize) -> u64 {
+        self.sizes[operation][class].load(Relaxed)
+    }
+    fn get_total(&self, operation: usize) -> u64 {
+        self.totals[operation].load(Relaxed)
+    }
+    fn get_align(&self, class: usize) -> u64 {
+        self.alignments[class].load(Relaxed)
+    }
+}
+
+macro_rules! impl_histogram {
+    ($tt:tt) => {
+        impl $tt {
+            /// Returns an iterator over all non-empty size classes recorded for `operation`.
+            ///
+            /// As `owns` doesn't take a size, the iterator is always empty for
+            /// [`Operation::Owns`].
+            pub fn sizes(&self, operation: Operation) -> impl Iterator<Item = SizeClass> + '_ {
+                let operation = operation_index(operation);
+                (0..CLASS_COUNT)
+                    .filter_map(move |class| {
+                        Some(SizeClass::size(class, self.get_size(operation?, class)))
+                    })
+                    .filter(|class| class.count != 0)
+            }
+
+            /// Returns the sum of all sizes recorded for `operation`.
+            ///
+            /// This is always `0` for [`Operation::Owns`].
+            pub fn total_size(&self, operation: Operation) -> u64 {
+                operation_index(operation).map_or(0, |operation| self.get_total(operation))
+            }
+
+            /// Returns an iterator over all non-empty alignment classes recorded by `alloc`.
+            pub fn alignments(&self) -> impl Iterator<Item = SizeClass> + '_ {
+                (0..CLASS_COUNT)
+                    .map(move |class| SizeClass::align(class, self.get_align(class)))
+                    .filter(|class| class.count != 0)
+            }
+        }
+
+        impl fmt::Display for $tt {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                writeln!(
+                    f,
+                    "{:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                    "min", "max", "alloc", "dealloc", "grow", "shrink"
+                )?;
+                for class in 0..CLASS_COUNT {
+                    let counts = [
+                        self.get_size(0, class),
+                        self.get_size(1, class),
+                        self.get_size(2, class),
+                        self.get_size(3, class),
+                    ];
+                    if counts.iter().any(|&count| count != 0) {
+                        let class = SizeClass::size(class, 0);
+                        writeln!(
+                            f,
+                            "{:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                            class.min,
+                            Max(class.max),
+                            counts[0],
+                            counts[1],
+                            counts[2],
+                            counts[3]
+                        )?;
+                    }
+                }
+                writeln!(f)?;
+                writeln!(f, "{:>12} {:>12}", "alignment", "alloc")?;
+                for class in self.alignments() {
+                    writeln!(f, "{:>12} {:>12}", class.min, class.count)?;
+                }
+                Ok(())
+            }
+        }
+
+        unsafe impl CallbackRef for $tt {
+            #[inline]
+            fn alloc(
+                &self,
+                layout: Layout,
+                _init: AllocInit,
+                result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
+            ) {
+                if result.is_ok() {
+                    self.increment_size(0, layout.size());
+                    self.increment_align(align_class(layout.align()));
+                }
+            }
+
+            #[inline]
+            fn dealloc(
+                &self,
+                _ptr: NonNull<u8>,
+                layout: Layout,
+                _location: &'static Location<'static>,
+            ) {
+                self.increment_size(1, layout.size());
+            }
+
+            #[inline]
+            fn grow(
+                &self,
+                _ptr: NonNull<u8>,
+                _layout: Layout,
+                new_size: usize,
+                _placement: ReallocPlacement,
+                _init: AllocInit,
+                result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
+            ) {
+                if result.is_ok() {
+                    self.increment_size(2, new_size);
+                }
+            }
+
+            #[inline]
+            fn shrink(
+                &self,
+                _ptr: NonNull<u8>,
+                _layout: Layout,
+                new_size: usize,
+                _placement: ReallocPlacement,
+                result: Result<MemoryBlock, AllocErr>,
+                _location: &'static Location<'static>,
+            ) {
+                if result.is_ok() {
+                    self.increment_size(3, new_size);
+                }
+            }
+
+            #[inline]
+            fn owns(&self, _success: bool) {}
+        }
+    };
+}
+
+impl_histogram!(Histogram);
+impl_histogram!(AtomicHistogram);
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+
+    #[test]
+    fn classes() {
+        assert_eq!(size_class(0), 0);
+        assert_eq!(size_class(1), 0);
+        assert_eq!(size_class(2), 1);
+        assert_eq!(size_class(3), 2);
+        assert_eq!(size_class(4), 2);
+        assert_eq!(size_class(5), 3);
+        assert_eq!(size_class(1 << 30), 30);
+        assert_eq!(size_class((1 << 30) + 1), 31);
+        assert_eq!(size_class(usize::MAX), 31);
+
+        assert_eq!(SizeClass::size(0, 0).to_string(), "0..=1");
+        assert_eq!(SizeClass::size(1, 0).to_string(), "2");
+        assert_eq!(SizeClass::size(3, 0).to_string(), "5..=8");
+        assert_eq!(SizeClass::size(31, 0).to_string(), "1073741825..");
+        assert_eq!(SizeClass::align(4, 0).to_string(), "16");
+    }
+
+    fn record(callbacks: &impl CallbackRef) {
+        let memory = MemoryBlock {
+            ptr: NonNull::dangling(),
+            size: 64,
+        };
+        callbacks.alloc(
+            Layout::new::<u32>(),
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        callbacks.alloc(
+            Layout::new::<u64>(),
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        callbacks.alloc(
+            Layout::new::<u64>(),
+            AllocInit::Uninitialized,
+            Err(AllocErr),
+            Location::caller(),
+        );
+        callbacks.grow(
+            memory.ptr,
+            Layout::new::<u64>(),
+            64,
+            ReallocPlacement::MayMove,
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        callbacks.shrink(
+            memory.ptr,
+            Layout::new::<[u64; 8]>(),
+            2,
+            ReallocPlacement::InPlace,
+            Err(AllocErr),
+            Location::caller(),
+        );
+        callbacks.dealloc(memory.ptr, Layout::new::<[u64; 8]>(), Location::caller());
+        callbacks.dealloc(memory.ptr, Layout::new::<u32>(), Location::caller());
+    }
+
+    #[test]
+    fn histogram() {
+        let histogram = Histogram::default();
+        let atomic_histogram = AtomicHistogram::default();
+        record(&histogram);
+        record(&atomic_histogram);
+
+        let allocs = histogram.sizes(Operation::Alloc).collect::<Vec<_>>();
+        assert_eq!(
+            allocs,
+            atomic_histogram.sizes(Operation::Alloc).collect::<Vec<_>>()
+        );
+        assert_eq!(allocs, [
+            SizeClass {
+                min: 3,
+                max: 4,
+                count: 1
+            },
+            SizeClass {
+                min: 5,
+                max: 8,
+                count: 1
+            }
+        ]);
+        assert_eq!(histogram.sizes(Operation::Dealloc).count(), 2);
+        assert_eq!(histogram.sizes(Operation::Grow).count(), 1);
+        assert_eq!(histogram.sizes(Operation::Shrink).count(), 0);
+        assert_eq!(histogram.sizes(Operation::Owns).count(), 0);
+
+        assert_eq!(histogram.total_size(Operation::Alloc), 12);
+        assert_eq!(atomic_histogram.total_size(Operation::Dealloc), 68);
+        assert_eq!(histogram.total_size(Operation::Grow), 64);
+        assert_eq!(histogram.total_size(Operation::Shrink), 0);
+        assert_eq!(histogram.total_size(Operation::Owns), 0);
+
+        let alignments = atomic_histogram.alignments().collect::<Vec<_>>();
+        assert_eq!(alignments, [
+            SizeClass {
+                min: 4,
+                max: 4,
+                count: 1
+            },
+            SizeClass {
+                min: 8,
+                max: 8,
+                count: 1
+            }
+        ]);
+
+        assert_eq!(histogram.to_string(), atomic_histogram.to_string());
+        let table = histogram.to_string();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[1].split_whitespace().collect::<Vec<_>>(), [
+            "3", "4", "1", "1", "0", "0"
+        ]);
+        assert_eq!(lines[3].split_whitespace().collect::<Vec<_>>(), [
+            "33", "64", "0", "1", "1", "0"
+        ]);
+    }
+}
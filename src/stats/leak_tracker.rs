@@ -0,0 +1,356 @@
+use crate::CallbackRef;
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{
+    alloc::{AllocErr, AllocInit, Layout, MemoryBlock, ReallocPlacement},
+    cell::RefCell,
+    fmt::{self, Write},
+    panic::Location,
+    ptr::NonNull,
+};
+
+/// A block of memory tracked by [`LeakTracker`], which was not deallocated yet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Leak {
+    /// The pointer to the block.
+    pub ptr: NonNull<u8>,
+    /// The layout the block was requested with.
+    pub layout: Layout,
+    /// The actual size of the block returned by the allocator.
+    pub size: usize,
+    /// The location of the call, which returned the block.
+    pub location: &'static Location<'static>,
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes (align {}) at {:?}, allocated at {}",
+            self.layout.size(),
+            self.layout.align(),
+            self.ptr,
+            self.location
+        )
+    }
+}
+
+/// Function called by [`LeakTracker`], when it's dropped while blocks are still allocated.
+pub type LeakHandler = fn(&[Leak]);
+
+fn panic_on_leak(leaks: &[Leak]) {
+    let mut report = String::new();
+    write_report(&mut report, leaks).expect("Could not write the leak report");
+    panic!("{}", report)
+}
+
+fn write_report(f: &mut impl Write, leaks: &[Leak]) -> fmt::Result {
+    write!(f, "{} allocation(s) were not freed:", leaks.len())?;
+    for leak in leaks {
+        write!(f, "\n- {}", leak)?;
+    }
+    Ok(())
+}
+
+/// Records all live blocks to report memory leaks.
+///
+/// Every block returned by `alloc`, `grow`, or `shrink` is stored together with the location
+/// passed by [`Proxy`]. Blocks are removed again on `dealloc`, or when they are moved by `grow`
+/// or `shrink`.
+///
+/// The remaining blocks can be queried with [`leaks`] or formatted with [`report`]. When the
+/// tracker is dropped while blocks are still allocated, the handler is called. By default, the
+/// handler panics with the report.
+///
+/// As the blocks are stored in a map, `LeakTracker` requires the **"alloc"-feature** and should
+/// not be used for the global allocator.
+///
+/// [`Proxy`]: crate::Proxy
+/// [`leaks`]: Self::leaks
+/// [`report`]: Self::report
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{stats::LeakTracker, CallbackRef, Proxy};
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let tracker = LeakTracker::with_handler(|_| {});
+/// let mut alloc = Proxy {
+///     alloc: System,
+///     callbacks: tracker.by_ref(),
+/// };
+///
+/// let memory = alloc.alloc(Layout::new::<u64>(), AllocInit::Uninitialized)?;
+/// assert_eq!(tracker.leaks()[0].layout, Layout::new::<u64>());
+/// assert!(tracker.report().is_some());
+///
+/// unsafe { alloc.dealloc(memory.ptr, Layout::new::<u64>()) };
+/// assert!(tracker.is_empty());
+/// assert_eq!(tracker.report(), None);
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct LeakTracker {
+    blocks: RefCell<BTreeMap<NonNull<u8>, Leak>>,
+    handler: LeakHandler,
+}
+
+impl LeakTracker {
+    /// Creates a new `LeakTracker`, which panics when dropped with blocks still allocated.
+    pub const fn new() -> Self {
+        Self::with_handler(panic_on_leak)
+    }
+
+    /// Creates a new `LeakTracker`, which calls `handler` when dropped with blocks still
+    /// allocated.
+    pub const fn with_handler(handler: LeakHandler) -> Self {
+        Self {
+            blocks: RefCell::new(BTreeMap::new()),
+            handler,
+        }
+    }
+
+    /// Returns the number of blocks currently allocated.
+    pub fn len(&self) -> usize {
+        self.blocks.borrow().len()
+    }
+
+    /// Returns `true` if no block is currently allocated.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.borrow().is_empty()
+    }
+
+    /// Returns all blocks currently allocated ordered by their address.
+    pub fn leaks(&self) -> Vec<Leak> {
+        self.blocks.borrow().values().copied().collect()
+    }
+
+    /// Lists every block currently allocated with its size, alignment, and location, or returns
+    /// `None` if all blocks were deallocated.
+    pub fn report(&self) -> Option<String> {
+        let leaks = self.leaks();
+        if leaks.is_empty() {
+            return None;
+        }
+        let mut report = String::new();
+        write_report(&mut report, &leaks).expect("Could not write the leak report");
+        Some(report)
+    }
+
+    /// Stops tracking all currently allocated blocks.
+    pub fn clear(&self) {
+        self.blocks.borrow_mut().clear()
+    }
+
+    fn insert(&self, memory: MemoryBlock, layout: Layout, location: &'static Location<'static>) {
+        self.blocks.borrow_mut().insert(memory.ptr, Leak {
+            ptr: memory.ptr,
+            layout,
+            size: memory.size,
+            location,
+        });
+    }
+
+    fn remove(&self, ptr: NonNull<u8>) {
+        self.blocks.borrow_mut().remove(&ptr);
+    }
+}
+
+impl Default for LeakTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for LeakTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeakTracker")
+            .field("leaks", &self.leaks())
+            .finish()
+    }
+}
+
+impl Drop for LeakTracker {
+    fn drop(&mut self) {
+        let leaks = self.leaks();
+        if !leaks.is_empty() {
+            (self.handler)(&leaks)
+        }
+    }
+}
+
+unsafe impl CallbackRef for LeakTracker {
+    fn alloc(
+        &self,
+        layout: Layout,
+        _init: AllocInit,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        if let Ok(memory) = result {
+            self.insert(memory, layout, location)
+        }
+    }
+
+    fn dealloc(&self, ptr: NonNull<u8>, _layout: Layout, _location: &'static Location<'static>) {
+        self.remove(ptr)
+    }
+
+    fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        _placement: ReallocPlacement,
+        _init: AllocInit,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        if let Ok(memory) = result {
+            self.remove(ptr);
+            let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+            self.insert(memory, new_layout, location)
+        }
+    }
+
+    fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        _placement: ReallocPlacement,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        if let Ok(memory) = result {
+            self.remove(ptr);
+            let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+            self.insert(memory, new_layout, location)
+        }
+    }
+
+    fn owns(&self, _success: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::{Proxy, Region};
+    use core::{alloc::AllocRef, cell::Cell};
+
+    thread_local! {
+        static NUM_LEAKS: Cell<usize> = Cell::new(0);
+    }
+
+    fn record(leaks: &[Leak]) {
+        NUM_LEAKS.with(|num_leaks| num_leaks.set(leaks.len()));
+    }
+
+    #[test]
+    fn leaks() {
+        let tracker = LeakTracker::new();
+        let mut data = [0; 32];
+        let mut alloc = Proxy {
+            alloc: Region::new(&mut data),
+            callbacks: tracker.by_ref(),
+        };
+        let layout = Layout::new::<[u8; 8]>();
+
+        let (first, line) = (alloc.alloc(layout, AllocInit::Uninitialized), line!());
+        let first = first.expect("Could not allocate 8 bytes");
+        let second = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        assert_eq!(tracker.len(), 2);
+
+        unsafe { alloc.dealloc(second.ptr, layout) };
+        let leaks = tracker.leaks();
+        assert_eq!(leaks.len(), 1);
+        assert_eq!(leaks[0].ptr, first.ptr);
+        assert_eq!(leaks[0].layout, layout);
+        assert_eq!(leaks[0].location.file(), file!());
+        assert_eq!(leaks[0].location.line(), line);
+
+        let report = tracker.report().expect("No leaks were reported");
+        assert!(report.starts_with("1 allocation(s) were not freed:\n- 8 bytes (align 1) at"));
+
+        unsafe { alloc.dealloc(first.ptr, layout) };
+        assert!(tracker.is_empty());
+        assert_eq!(tracker.report(), None);
+    }
+
+    #[test]
+    fn realloc() {
+        let tracker = LeakTracker::new();
+        let mut data = [0; 32];
+        let mut alloc = Proxy {
+            alloc: Region::new(&mut data),
+            callbacks: tracker.by_ref(),
+        };
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 8]>(),
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 16 bytes");
+            assert_eq!(tracker.leaks()[0].layout, Layout::new::<[u8; 16]>());
+            assert_eq!(tracker.leaks()[0].location.file(), file!());
+
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    4,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 4 bytes");
+            assert_eq!(tracker.len(), 1);
+            assert_eq!(tracker.leaks()[0].layout, Layout::new::<[u8; 4]>());
+
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 4]>());
+        }
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn handler() {
+        let mut data = [0; 32];
+        let tracker = LeakTracker::with_handler(record);
+        let mut alloc = Proxy {
+            alloc: Region::new(&mut data),
+            callbacks: tracker,
+        };
+        alloc
+            .alloc(Layout::new::<u32>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 4 bytes");
+        alloc
+            .alloc(Layout::new::<u32>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 4 bytes");
+
+        drop(alloc);
+        assert_eq!(NUM_LEAKS.with(Cell::get), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 allocation(s) were not freed")]
+    fn panic_on_drop() {
+        let mut data = [0; 32];
+        let mut alloc = Proxy {
+            alloc: Region::new(&mut data),
+            callbacks: LeakTracker::new(),
+        };
+        alloc
+            .alloc(Layout::new::<u32>(), AllocInit::Uninitialized)
+            .expect("Could not allocate 4 bytes");
+    }
+}
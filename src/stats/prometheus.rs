@@ -0,0 +1,746 @@
+#[cfg(any(doc, feature = "alloc"))]
+use super::CallSiteCounter;
+use super::{
+    histogram::{size_class, CLASS_COUNT},
+    AtomicByteCounter,
+    AtomicCounter,
+    AtomicHistogram,
+    ByteCounter,
+    ByteCounterSnapshot,
+    Counter,
+    CounterSnapshot,
+    FilteredAtomicCounter,
+    FilteredCounter,
+    FilteredCounterSnapshot,
+    FilteredStatKey,
+    Histogram,
+    Operation,
+    SizedFilteredAtomicCounter,
+    SizedFilteredCounter,
+    SizedFilteredCounterSnapshot,
+    FILTERED_STATS,
+    FILTERED_STAT_COUNT,
+};
+use core::{
+    alloc::{AllocInit, ReallocPlacement},
+    fmt::{self, Write},
+};
+
+#[derive(Copy, Clone)]
+enum Label {
+    Operation,
+    Init,
+    Placement,
+    Result,
+    Success,
+}
+
+type Labels<'l> = &'l [(Label, &'static str)];
+
+/// Derives the labels of a filtered statistic from its key.
+fn filtered_labels(key: FilteredStatKey) -> ([(Label, &'static str); 4], usize) {
+    let (_, operation, init, placement, success, _) = key;
+    let mut labels = [(Label::Operation, ""); 4];
+    let mut len = 0;
+    let mut push = |label, value| {
+        labels[len] = (label, value);
+        len += 1;
+    };
+
+    push(Label::Operation, match operation {
+        Operation::Alloc => "alloc",
+        Operation::Dealloc => "dealloc",
+        Operation::Grow => "grow",
+        Operation::Shrink => "shrink",
+        Operation::Owns => "owns",
+    });
+    if let Some(placement) = placement {
+        push(Label::Placement, match placement {
+            ReallocPlacement::MayMove => "may_move",
+            ReallocPlacement::InPlace => "in_place",
+        });
+    }
+    if let Some(init) = init {
+        push(Label::Init, match init {
+            AllocInit::Uninitialized => "uninitialized",
+            AllocInit::Zeroed => "zeroed",
+        });
+    }
+    match operation {
+        Operation::Dealloc => {}
+        Operation::Owns => push(Label::Success, if success { "true" } else { "false" }),
+        _ => push(Label::Result, if success { "ok" } else { "err" }),
+    }
+    (labels, len)
+}
+
+/// Writes the statistics of the counters in [`stats`] in the [Prometheus text format].
+///
+/// Every metric name is prefixed with the namespace, which defaults to `"alloc"`. The calls of
+/// [`Counter`] and [`FilteredCounter`] are exported as `<namespace>_calls_total` with an
+/// `operation` label. The filtered counters additionally label the calls with `init`,
+/// `placement`, and `result` (`success` for `owns`), where applicable. The name of the calls
+/// metric and the label names can be changed with [`with_calls_name`] and [`with_label_names`].
+///
+/// [`ByteCounter`] is exported as `<namespace>_requested_bytes_total`,
+/// `<namespace>_allocated_bytes_total`, `<namespace>_moved_bytes_total`, `<namespace>_live_bytes`,
+/// and `<namespace>_peak_bytes`. The sizes recorded by [`Histogram`] are exported as the histogram
+/// `<namespace>_size_bytes` with an `operation` label, [`CallSiteCounter`] is exported as
+/// `<namespace>_call_site_calls_total`, `<namespace>_call_site_allocated_bytes_total`, and
+/// `<namespace>_call_site_deallocated_bytes_total` with `file` and `line` labels.
+///
+/// Constant labels, e.g. the name of the allocator, are prepended to every sample. Neither the
+/// namespace nor the label names are validated, label values are escaped.
+///
+/// Requires the **"prometheus"-feature**.
+///
+/// [`stats`]: crate::stats
+/// [Prometheus text format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+/// [`with_calls_name`]: Self::with_calls_name
+/// [`with_label_names`]: Self::with_label_names
+/// [`Histogram`]: crate::stats::Histogram
+/// [`CallSiteCounter`]: crate::stats::CallSiteCounter
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{
+///     stats::{Counter, Prometheus},
+///     CallbackRef,
+///     Proxy,
+/// };
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let counter = Counter::default();
+/// let mut alloc = Proxy {
+///     alloc: System,
+///     callbacks: counter.by_ref(),
+/// };
+///
+/// let memory = alloc.alloc(Layout::new::<u64>(), AllocInit::Uninitialized)?;
+/// unsafe { alloc.dealloc(memory.ptr, Layout::new::<u64>()) };
+///
+/// let labels = [("allocator", "system")];
+/// let exporter = Prometheus::new("app_alloc").with_labels(&labels);
+/// let mut text = String::new();
+/// exporter
+///     .write(&mut text, &counter)
+///     .expect("Could not write metrics");
+/// assert!(text.contains("app_alloc_calls_total{allocator=\"system\",operation=\"alloc\"} 1\n"));
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Prometheus<'a> {
+    namespace: &'a str,
+    labels: &'a [(&'a str, &'a str)],
+    calls_name: &'a str,
+    label_names: PrometheusLabels<'a>,
+}
+
+/// The names of the labels used by [`Prometheus`] to break down the calls to an allocator.
+///
+/// Requires the **"prometheus"-feature**.
+///
+/// ## Examples
+///
+/// ```rust
+/// use alloc_compose::stats::{Prometheus, PrometheusLabels};
+///
+/// let exporter = Prometheus::default()
+///     .with_calls_name("requests_total")
+///     .with_label_names(PrometheusLabels {
+///         operation: "method",
+///         ..PrometheusLabels::default()
+///     });
+/// # let _ = exporter;
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PrometheusLabels<'a> {
+    /// The label for the method of the allocator, defaults to `"operation"`.
+    pub operation: &'a str,
+    /// The label for the `AllocInit` of `alloc` and `grow`, defaults to `"init"`.
+    pub init: &'a str,
+    /// The label for the `ReallocPlacement` of `grow` and `shrink`, defaults to `"placement"`.
+    pub placement: &'a str,
+    /// The label for the result of `alloc`, `grow`, and `shrink`, defaults to `"result"`.
+    pub result: &'a str,
+    /// The label for the result of `owns`, defaults to `"success"`.
+    pub success: &'a str,
+}
+
+impl PrometheusLabels<'_> {
+    const DEFAULT: Self = Self {
+        operation: "operation",
+        init: "init",
+        placement: "placement",
+        result: "result",
+        success: "success",
+    };
+
+    fn get(&self, label: Label) -> &str {
+        match label {
+            Label::Operation => self.operation,
+            Label::Init => self.init,
+            Label::Placement => self.placement,
+            Label::Result => self.result,
+            Label::Success => self.success,
+        }
+    }
+}
+
+impl Default for PrometheusLabels<'_> {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl<'a> Prometheus<'a> {
+    /// Creates a new exporter, which prefixes every metric name with `namespace`.
+    pub const fn new(namespace: &'a str) -> Self {
+        Self {
+            namespace,
+            labels: &[],
+            calls_name: "calls_total",
+            label_names: PrometheusLabels::DEFAULT,
+        }
+    }
+
+    /// Adds constant labels to every sample.
+    pub const fn with_labels(self, labels: &'a [(&'a str, &'a str)]) -> Self {
+        Self { labels, ..self }
+    }
+
+    /// Exports the calls as `<namespace>_<name>` instead of `<namespace>_calls_total`.
+    pub const fn with_calls_name(self, calls_name: &'a str) -> Self {
+        Self { calls_name, ..self }
+    }
+
+    /// Changes the names of the labels, which break down the calls.
+    pub const fn with_label_names(self, label_names: PrometheusLabels<'a>) -> Self {
+        Self {
+            label_names,
+            ..self
+        }
+    }
+
+    /// Writes the statistics of `metrics` to `f`.
+    pub fn write(&self, f: &mut impl Write, metrics: &impl PrometheusMetrics) -> fmt::Result {
+        metrics.write_metrics(self, f)
+    }
+
+    /// Renders the statistics of `metrics` into a `String`.
+    ///
+    /// Requires the **"alloc"-feature**.
+    #[cfg(any(doc, feature = "alloc"))]
+    #[cfg_attr(doc, doc(cfg(feature = "alloc")))]
+    pub fn render(&self, metrics: &impl PrometheusMetrics) -> alloc::string::String {
+        let mut text = alloc::string::String::new();
+        self.write(&mut text, metrics)
+            .expect("Could not write the metrics");
+        text
+    }
+
+    /// Writes the `HELP` and `TYPE` lines of a metric.
+    pub fn write_header(
+        &self,
+        f: &mut dyn Write,
+        name: &str,
+        kind: &str,
+        help: &str,
+    ) -> fmt::Result {
+        writeln!(f, "# HELP {}_{} {}", self.namespace, name, help)?;
+        writeln!(f, "# TYPE {}_{} {}", self.namespace, name, kind)
+    }
+
+    /// Writes a single sample of a metric with the constant labels followed by `labels`.
+    pub fn write_sample(
+        &self,
+        f: &mut dyn Write,
+        name: &str,
+        labels: &[(&str, &str)],
+        value: u64,
+    ) -> fmt::Result {
+        write!(f, "{}_{}", self.namespace, name)?;
+        let mut labels = self.labels.iter().chain(labels);
+        if let Some((label, value)) = labels.next() {
+            write!(f, "{{{}=\"", label)?;
+            write_escaped(f, value)?;
+            for (label, value) in labels {
+                write!(f, "\",{}=\"", label)?;
+                write_escaped(f, value)?;
+            }
+            f.write_str("\"}")?;
+        }
+        writeln!(f, " {}", value)
+    }
+
+    fn write_calls(&self, f: &mut dyn Write, samples: &[(Labels<'_>, u64)]) -> fmt::Result {
+        self.write_header(
+            f,
+            self.calls_name,
+            "counter",
+            "The number of calls to the allocator.",
+        )?;
+        for &(labels, value) in samples {
+            let mut named = [("", ""); 4];
+            for (named, &(label, value)) in named.iter_mut().zip(labels) {
+                *named = (self.label_names.get(label), value);
+            }
+            self.write_sample(f, self.calls_name, &named[..labels.len()], value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Prometheus<'_> {
+    fn default() -> Self {
+        Self::new("alloc")
+    }
+}
+
+fn write_escaped(f: &mut dyn Write, value: &str) -> fmt::Result {
+    for c in value.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '"' => f.write_str("\\\"")?,
+            '\n' => f.write_str("\\n")?,
+            c => f.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Statistics, which can be written by the [`Prometheus`] exporter.
+///
+/// Requires the **"prometheus"-feature**.
+pub trait PrometheusMetrics {
+    /// Writes all metrics with `exporter` to `f`.
+    fn write_metrics(&self, exporter: &Prometheus<'_>, f: &mut dyn Write) -> fmt::Result;
+}
+
+impl PrometheusMetrics for CounterSnapshot {
+    fn write_metrics(&self, exporter: &Prometheus<'_>, f: &mut dyn Write) -> fmt::Result {
+        exporter.write_calls(f, &[
+            (&[(Label::Operation, "alloc")], self.num_allocs()),
+            (&[(Label::Operation, "dealloc")], self.num_deallocs()),
+            (&[(Label::Operation, "grow")], self.num_grows()),
+            (&[(Label::Operation, "shrink")], self.num_shrinks()),
+            (&[(Label::Operation, "owns")], self.num_owns()),
+        ])
+    }
+}
+
+impl PrometheusMetrics for FilteredCounterSnapshot {
+    fn write_metrics(&self, exporter: &Prometheus<'_>, f: &mut dyn Write) -> fmt::Result {
+        let mut labels = [([(Label::Operation, ""); 4], 0); FILTERED_STAT_COUNT];
+        for (labels, &key) in labels.iter_mut().zip(FILTERED_STATS.iter()) {
+            *labels = filtered_labels(key);
+        }
+        let mut samples: [(Labels<'_>, u64); FILTERED_STAT_COUNT] = [(&[], 0); FILTERED_STAT_COUNT];
+        for ((sample, (labels, len)), key) in samples.iter_mut().zip(&labels).zip(&FILTERED_STATS) {
+            *sample = (&labels[..*len], self.get(key.0));
+        }
+        exporter.write_calls(f, &samples)
+    }
+}
+
+/// Exports the same metrics as [`FilteredCounterSnapshot`] without the breakdown by size.
+impl PrometheusMetrics for SizedFilteredCounterSnapshot {
+    fn write_metrics(&self, exporter: &Prometheus<'_>, f: &mut dyn Write) -> fmt::Result {
+        FilteredCounterSnapshot::from(*self).write_metrics(exporter, f)
+    }
+}
+
+macro_rules! impl_prometheus_metrics_via_snapshot {
+    ($tt:tt) => {
+        impl PrometheusMetrics for $tt {
+            fn write_metrics(&self, exporter: &Prometheus<'_>, f: &mut dyn Write) -> fmt::Result {
+                self.snapshot().write_metrics(exporter, f)
+            }
+        }
+    };
+}
+
+impl_prometheus_metrics_via_snapshot!(Counter);
+impl_prometheus_metrics_via_snapshot!(AtomicCounter);
+impl_prometheus_metrics_via_snapshot!(FilteredCounter);
+impl_prometheus_metrics_via_snapshot!(FilteredAtomicCounter);
+impl_prometheus_metrics_via_snapshot!(SizedFilteredCounter);
+impl_prometheus_metrics_via_snapshot!(SizedFilteredAtomicCounter);
+impl_prometheus_metrics_via_snapshot!(ByteCounter);
+impl_prometheus_metrics_via_snapshot!(AtomicByteCounter);
+
+impl PrometheusMetrics for ByteCounterSnapshot {
+    fn write_metrics(&self, exporter: &Prometheus<'_>, f: &mut dyn Write) -> fmt::Result {
+        let metrics = [
+            (
+                "requested_bytes_total",
+                "counter",
+                "The number of bytes requested by successful `alloc` and `grow` calls.",
+                self.total_requested(),
+            ),
+            (
+                "allocated_bytes_total",
+                "counter",
+                "The number of bytes returned by successful `alloc`, `grow`, and `shrink` calls.",
+                self.total_allocated(),
+            ),
+            (
+                "moved_bytes_total",
+                "counter",
+                "The number of bytes copied by `grow` and `shrink`.",
+                self.moved(),
+            ),
+            (
+                "live_bytes",
+                "gauge",
+                "The number of bytes currently allocated.",
+                self.live(),
+            ),
+            (
+                "peak_bytes",
+                "gauge",
+                "The highest number of bytes allocated at the same time.",
+                self.peak(),
+            ),
+        ];
+        for &(name, kind, help, value) in &metrics {
+            exporter.write_header(f, name, kind, help)?;
+            exporter.write_sample(f, name, &[], value)?;
+        }
+        Ok(())
+    }
+}
+
+/// The upper bounds of the size classes of a [`Histogram`].
+const BUCKETS: [&str; CLASS_COUNT] = [
+    "1",
+    "2",
+    "4",
+    "8",
+    "16",
+    "32",
+    "64",
+    "128",
+    "256",
+    "512",
+    "1024",
+    "2048",
+    "4096",
+    "8192",
+    "16384",
+    "32768",
+    "65536",
+    "131072",
+    "262144",
+    "524288",
+    "1048576",
+    "2097152",
+    "4194304",
+    "8388608",
+    "16777216",
+    "33554432",
+    "67108864",
+    "134217728",
+    "268435456",
+    "536870912",
+    "1073741824",
+    "+Inf",
+];
+
+const OPERATIONS: [(Operation, &str); 4] = [
+    (Operation::Alloc, "alloc"),
+    (Operation::Dealloc, "dealloc"),
+    (Operation::Grow, "grow"),
+    (Operation::Shrink, "shrink"),
+];
+
+macro_rules! impl_histogram_prometheus_metrics {
+    ($tt:tt) => {
+        /// Exports the sizes as a histogram with one bucket per size class. The alignments are
+        /// not exported.
+        impl PrometheusMetrics for $tt {
+            fn write_metrics(&self, exporter: &Prometheus<'_>, f: &mut dyn Write) -> fmt::Result {
+                let operation_label = exporter.label_names.operation;
+                exporter.write_header(
+                    f,
+                    "size_bytes",
+                    "histogram",
+                    "The sizes passed to the allocator.",
+                )?;
+                for &(operation, name) in &OPERATIONS {
+                    let mut counts = [0; CLASS_COUNT];
+                    for class in self.sizes(operation) {
+                        counts[size_class(class.max)] = class.count;
+                    }
+                    let mut count = 0;
+                    for (&bucket, &class_count) in BUCKETS.iter().zip(&counts) {
+                        count += class_count;
+                        exporter.write_sample(
+                            f,
+                            "size_bytes_bucket",
+                            &[(operation_label, name), ("le", bucket)],
+                            count,
+                        )?;
+                    }
+                    let labels = [(operation_label, name)];
+                    exporter.write_sample(
+                        f,
+                        "size_bytes_sum",
+                        &labels,
+                        self.total_size(operation),
+                    )?;
+                    exporter.write_sample(f, "size_bytes_count", &labels, count)?;
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_histogram_prometheus_metrics!(Histogram);
+impl_histogram_prometheus_metrics!(AtomicHistogram);
+
+#[cfg(any(doc, feature = "alloc"))]
+#[cfg_attr(doc, doc(cfg(feature = "alloc")))]
+impl PrometheusMetrics for CallSiteCounter {
+    fn write_metrics(&self, exporter: &Prometheus<'_>, f: &mut dyn Write) -> fmt::Result {
+        use alloc::{string::ToString, vec::Vec};
+
+        let call_sites = self.call_sites();
+        let lines = call_sites
+            .iter()
+            .map(|call_site| call_site.line.to_string())
+            .collect::<Vec<_>>();
+        let operation_label = exporter.label_names.operation;
+
+        exporter.write_header(
+            f,
+            "call_site_calls_total",
+            "counter",
+            "The number of calls to the allocator per call site.",
+        )?;
+        for (call_site, line) in call_sites.iter().zip(&lines) {
+            let calls = [
+                ("alloc", call_site.num_allocs),
+                ("dealloc", call_site.num_deallocs),
+                ("grow", call_site.num_grows),
+                ("shrink", call_site.num_shrinks),
+            ];
+            for &(operation, value) in &calls {
+                exporter.write_sample(
+                    f,
+                    "call_site_calls_total",
+                    &[
+                        ("file", call_site.file),
+                        ("line", line),
+                        (operation_label, operation),
+                    ],
+                    value,
+                )?;
+            }
+        }
+
+        let metrics = [
+            (
+                "call_site_allocated_bytes_total",
+                "The number of bytes allocated per call site.",
+                true,
+            ),
+            (
+                "call_site_deallocated_bytes_total",
+                "The number of bytes released per call site.",
+                false,
+            ),
+        ];
+        for &(name, help, allocated) in &metrics {
+            exporter.write_header(f, name, "counter", help)?;
+            for (call_site, line) in call_sites.iter().zip(&lines) {
+                let value = if allocated {
+                    call_site.allocated_bytes
+                } else {
+                    call_site.deallocated_bytes
+                };
+                exporter.write_sample(
+                    f,
+                    name,
+                    &[("file", call_site.file), ("line", line)],
+                    value,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::CallbackRef;
+    use core::{
+        alloc::{AllocErr, AllocInit, Layout, MemoryBlock, ReallocPlacement},
+        panic::Location,
+        ptr::NonNull,
+    };
+
+    fn render(exporter: Prometheus<'_>, metrics: &impl PrometheusMetrics) -> String {
+        let mut text = String::new();
+        exporter
+            .write(&mut text, metrics)
+            .expect("Could not write metrics");
+        text
+    }
+
+    fn record(callbacks: &impl CallbackRef) {
+        let layout = Layout::new::<[u8; 8]>();
+        let memory = MemoryBlock {
+            ptr: NonNull::dangling(),
+            size: 8,
+        };
+        callbacks.alloc(layout, AllocInit::Zeroed, Ok(memory), Location::caller());
+        callbacks.alloc(
+            layout,
+            AllocInit::Uninitialized,
+            Err(AllocErr),
+            Location::caller(),
+        );
+        callbacks.shrink(
+            memory.ptr,
+            layout,
+            4,
+            ReallocPlacement::InPlace,
+            Ok(memory),
+            Location::caller(),
+        );
+        callbacks.owns(false);
+    }
+
+    #[test]
+    fn counter() {
+        let counter = Counter::default();
+        record(&counter);
+
+        let labels = [("allocator", "my \"region\"")];
+        let text = render(Prometheus::new("test").with_labels(&labels), &counter);
+        assert_eq!(
+            text,
+            "# HELP test_calls_total The number of calls to the allocator.\n# TYPE \
+             test_calls_total counter\ntest_calls_total{allocator=\"my \
+             \\\"region\\\"\",operation=\"alloc\"} 2\ntest_calls_total{allocator=\"my \
+             \\\"region\\\"\",operation=\"dealloc\"} 0\ntest_calls_total{allocator=\"my \
+             \\\"region\\\"\",operation=\"grow\"} 0\ntest_calls_total{allocator=\"my \
+             \\\"region\\\"\",operation=\"shrink\"} 1\ntest_calls_total{allocator=\"my \
+             \\\"region\\\"\",operation=\"owns\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn filtered_counter() {
+        let counter = FilteredAtomicCounter::default();
+        record(&counter);
+
+        let text = render(Prometheus::default(), &counter);
+        assert_eq!(text.lines().count(), 21);
+        assert!(text
+            .contains("alloc_calls_total{operation=\"alloc\",init=\"zeroed\",result=\"ok\"} 1\n"));
+        assert!(text.contains(
+            "alloc_calls_total{operation=\"alloc\",init=\"uninitialized\",result=\"err\"} 1\n"
+        ));
+        assert!(text.contains(
+            "alloc_calls_total{operation=\"shrink\",placement=\"in_place\",result=\"ok\"} 1\n"
+        ));
+        assert!(text.contains("alloc_calls_total{operation=\"owns\",success=\"false\"} 1\n"));
+        assert!(text.contains("alloc_calls_total{operation=\"dealloc\"} 0\n"));
+    }
+
+    #[test]
+    fn byte_counter() {
+        let counter = ByteCounter::default();
+        record(&counter);
+
+        let text = render(Prometheus::default(), &counter);
+        assert!(text.contains("# TYPE alloc_requested_bytes_total counter\n"));
+        assert!(text.contains("alloc_requested_bytes_total 8\n"));
+        assert!(text.contains("# TYPE alloc_live_bytes gauge\n"));
+        assert!(text.contains("alloc_live_bytes 4\n"));
+        assert!(text.contains("alloc_peak_bytes 8\n"));
+    }
+
+    #[test]
+    fn label_names() {
+        let counter = FilteredCounter::default();
+        record(&counter);
+
+        let exporter = Prometheus::new("test")
+            .with_calls_name("requests_total")
+            .with_label_names(PrometheusLabels {
+                operation: "method",
+                result: "status",
+                ..PrometheusLabels::default()
+            });
+        let text = render(exporter, &counter);
+        assert!(text.contains("# TYPE test_requests_total counter\n"));
+        assert!(text.contains(
+            "test_requests_total{method=\"alloc\",init=\"zeroed\",status=\"ok\"} 1\n"
+        ));
+        assert!(text.contains("test_requests_total{method=\"owns\",success=\"false\"} 1\n"));
+    }
+
+    #[test]
+    fn histogram() {
+        let histogram = AtomicHistogram::default();
+        record(&histogram);
+
+        let text = render(Prometheus::default(), &histogram);
+        assert_eq!(text.lines().count(), 2 + 4 * (CLASS_COUNT + 2));
+        assert!(text.contains("# TYPE alloc_size_bytes histogram\n"));
+        assert!(text.contains("alloc_size_bytes_bucket{operation=\"alloc\",le=\"4\"} 0\n"));
+        assert!(text.contains("alloc_size_bytes_bucket{operation=\"alloc\",le=\"8\"} 1\n"));
+        assert!(text.contains("alloc_size_bytes_bucket{operation=\"alloc\",le=\"+Inf\"} 1\n"));
+        assert!(text.contains("alloc_size_bytes_sum{operation=\"alloc\"} 8\n"));
+        assert!(text.contains("alloc_size_bytes_count{operation=\"alloc\"} 1\n"));
+        assert!(text.contains("alloc_size_bytes_bucket{operation=\"shrink\",le=\"4\"} 1\n"));
+        assert!(text.contains("alloc_size_bytes_sum{operation=\"shrink\"} 4\n"));
+        assert!(text.contains("alloc_size_bytes_count{operation=\"dealloc\"} 0\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn call_site_counter() {
+        let counter = CallSiteCounter::new();
+        let location = Location::caller();
+        counter.alloc(
+            Layout::new::<[u8; 8]>(),
+            AllocInit::Uninitialized,
+            Ok(MemoryBlock {
+                ptr: NonNull::dangling(),
+                size: 8,
+            }),
+            location,
+        );
+        counter.dealloc(NonNull::dangling(), Layout::new::<[u8; 8]>(), location);
+
+        let text = render(Prometheus::default(), &counter);
+        let labels = format!("file=\"{}\",line=\"{}\"", location.file(), location.line());
+        assert!(text.contains("# TYPE alloc_call_site_calls_total counter\n"));
+        assert!(text.contains(&format!(
+            "alloc_call_site_calls_total{{{},operation=\"alloc\"}} 1\n",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "alloc_call_site_calls_total{{{},operation=\"grow\"}} 0\n",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "alloc_call_site_allocated_bytes_total{{{}}} 8\n",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "alloc_call_site_deallocated_bytes_total{{{}}} 8\n",
+            labels
+        )));
+    }
+}
@@ -0,0 +1,128 @@
+use super::{
+    ByteCounterSnapshot,
+    CounterSnapshot,
+    FilteredCounterSnapshot,
+    SizedFilteredCounterSnapshot,
+    FILTERED_STATS,
+    FILTERED_STAT_COUNT,
+};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/// Serializes the snapshot as a struct with the fields `num_allocs`, `num_deallocs`,
+/// `num_grows`, `num_shrinks`, and `num_owns`.
+impl Serialize for CounterSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CounterSnapshot", 5)?;
+        state.serialize_field("num_allocs", &self.num_allocs())?;
+        state.serialize_field("num_deallocs", &self.num_deallocs())?;
+        state.serialize_field("num_grows", &self.num_grows())?;
+        state.serialize_field("num_shrinks", &self.num_shrinks())?;
+        state.serialize_field("num_owns", &self.num_owns())?;
+        state.end()
+    }
+}
+
+/// Serializes the snapshot as a struct with the fields `total_requested`, `total_allocated`,
+/// `live`, `peak`, and `moved`.
+impl Serialize for ByteCounterSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ByteCounterSnapshot", 5)?;
+        state.serialize_field("total_requested", &self.total_requested())?;
+        state.serialize_field("total_allocated", &self.total_allocated())?;
+        state.serialize_field("live", &self.live())?;
+        state.serialize_field("peak", &self.peak())?;
+        state.serialize_field("moved", &self.moved())?;
+        state.end()
+    }
+}
+
+/// Serializes the snapshot as a struct with one field for every combination of operation,
+/// `AllocInit`, `ReallocPlacement`, and result, e.g. `allocs_zeroed_ok` or
+/// `grows_in_place_uninitialized_err`.
+impl Serialize for FilteredCounterSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state =
+            serializer.serialize_struct("FilteredCounterSnapshot", FILTERED_STAT_COUNT)?;
+        for &(stat, .., name) in &FILTERED_STATS {
+            state.serialize_field(name, &self.get(stat))?;
+        }
+        state.end()
+    }
+}
+
+/// Serializes the snapshot like [`FilteredCounterSnapshot`] without the breakdown by size.
+impl Serialize for SizedFilteredCounterSnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FilteredCounterSnapshot::from(*self).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        stats::{ByteCounter, Counter, FilteredCounter},
+        CallbackRef,
+    };
+    use core::{
+        alloc::{AllocErr, AllocInit, Layout, MemoryBlock},
+        ptr::NonNull,
+    };
+    use std::panic::Location;
+
+    #[test]
+    fn counter() {
+        let counter = Counter::default();
+        counter.alloc(
+            Layout::new::<u8>(),
+            AllocInit::Uninitialized,
+            Err(AllocErr),
+            Location::caller(),
+        );
+        counter.owns(true);
+
+        let json = serde_json::to_string(&counter.snapshot()).expect("Could not serialize");
+        assert_eq!(
+            json,
+            r#"{"num_allocs":1,"num_deallocs":0,"num_grows":0,"num_shrinks":0,"num_owns":1}"#
+        );
+    }
+
+    #[test]
+    fn filtered_counter() {
+        let counter = FilteredCounter::default();
+        counter.alloc(
+            Layout::new::<u8>(),
+            AllocInit::Zeroed,
+            Err(AllocErr),
+            Location::caller(),
+        );
+
+        let json = serde_json::to_value(counter.snapshot()).expect("Could not serialize");
+        let fields = json.as_object().expect("Expected an object");
+        assert_eq!(fields.len(), 19);
+        assert_eq!(fields["allocs_zeroed_err"], 1);
+        assert_eq!(fields["allocs_zeroed_ok"], 0);
+        assert_eq!(fields["grows_in_place_uninitialized_err"], 0);
+    }
+
+    #[test]
+    fn byte_counter() {
+        let counter = ByteCounter::default();
+        let memory = MemoryBlock {
+            ptr: NonNull::dangling(),
+            size: 16,
+        };
+        counter.alloc(
+            Layout::new::<[u8; 12]>(),
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+
+        let json = serde_json::to_string(&counter.snapshot()).expect("Could not serialize");
+        assert_eq!(
+            json,
+            r#"{"total_requested":12,"total_allocated":16,"live":12,"peak":12,"moved":0}"#
+        );
+    }
+}
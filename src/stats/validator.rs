@@ -0,0 +1,557 @@
+use super::Operation;
+use crate::CallbackRef;
+use alloc::collections::BTreeMap;
+use core::{
+    alloc::{AllocErr, AllocInit, Layout, MemoryBlock, ReallocPlacement},
+    cell::RefCell,
+    fmt,
+    panic::Location,
+    ptr::NonNull,
+};
+
+/// The kind of a [`Violation`] detected by [`Validator`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The pointer was not returned by the allocator or was already deallocated.
+    UnknownBlock,
+    /// The alignment of the layout doesn't match the alignment the block was allocated with.
+    AlignmentMismatch { expected: usize, found: usize },
+    /// The size of the layout doesn't lie between the requested and the returned size.
+    SizeMismatch {
+        min: usize,
+        max: usize,
+        found: usize,
+    },
+    /// `new_size` is smaller than the old size when growing, or greater when shrinking.
+    InvalidNewSize { size: usize, new_size: usize },
+}
+
+/// A violation of the contract of `AllocRef` detected by [`Validator`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The method, which was called with invalid arguments.
+    pub operation: Operation,
+    /// The pointer passed to the method.
+    pub ptr: NonNull<u8>,
+    /// The kind of the violation.
+    pub kind: ViolationKind,
+    /// The location of the call.
+    pub location: &'static Location<'static>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.operation)?;
+        match self.kind {
+            ViolationKind::UnknownBlock => write!(
+                f,
+                "`ptr` must denote a block of memory currently allocated via this allocator, got \
+                 {:?}",
+                self.ptr
+            )?,
+            ViolationKind::AlignmentMismatch { expected, found } => write!(
+                f,
+                "`layout` must fit that block of memory. Expected alignment of {}, got {}",
+                expected, found
+            )?,
+            ViolationKind::SizeMismatch { min, max, found } if min == max => write!(
+                f,
+                "`layout` must fit that block of memory. Expected size of {}, got {}",
+                min, found
+            )?,
+            ViolationKind::SizeMismatch { min, max, found } => write!(
+                f,
+                "`layout` must fit that block of memory. Expected size between {}..={}, got {}",
+                min, max, found
+            )?,
+            ViolationKind::InvalidNewSize { size, new_size } if new_size < size => write!(
+                f,
+                "`new_size` must be greater than or equal to `layout.size()`, expected {} >= {}",
+                new_size, size
+            )?,
+            ViolationKind::InvalidNewSize { size, new_size } => write!(
+                f,
+                "`new_size` must be smaller than or equal to `layout.size()`, expected {} <= {}",
+                new_size, size
+            )?,
+        }
+        write!(f, " at {}", self.location)
+    }
+}
+
+/// Function called by [`Validator`], when a violation was detected.
+///
+/// When used with [`Proxy`], the handler has to diverge, e.g. by panicking or aborting.
+/// Otherwise, the invalid call is forwarded to the underlying allocator afterwards.
+///
+/// [`Proxy`]: crate::Proxy
+pub type ViolationHandler = fn(&Violation);
+
+fn panic_on_violation(violation: &Violation) {
+    panic!("{}", violation)
+}
+
+/// Validates the arguments passed to `dealloc`, `grow`, and `shrink`.
+///
+/// `Validator` records every block returned by the allocator and checks, that
+///   * the pointer passed to `dealloc`, `grow`, and `shrink` denotes a block currently allocated,
+///     which catches double frees,
+///   * the alignment of the layout matches the alignment the block was allocated with,
+///   * the size of the layout lies between the requested size and the size of the returned
+///     [`MemoryBlock`], and
+///   * `new_size` is greater than or equal to the old size in `grow`, and smaller than or equal
+///     in `shrink`.
+///
+/// Every violation is passed to the handler together with the location of the call. By default,
+/// the handler panics. As [`Proxy`] invokes the callbacks of `grow` and `shrink` after calling
+/// the underlying allocator, only `dealloc` is validated before the memory is touched. A handler,
+/// which returns, can't stop `Proxy` from passing an invalid `dealloc` on, which is undefined
+/// behavior.
+///
+/// As the blocks are stored in a map, `Validator` requires the **"alloc"-feature** and should
+/// not be used for the global allocator.
+///
+/// [`Proxy`]: crate::Proxy
+///
+/// ## Examples
+///
+/// ```rust
+/// #![feature(allocator_api)]
+///
+/// use alloc_compose::{stats::Validator, CallbackRef, Proxy};
+/// use std::alloc::{AllocInit, AllocRef, Layout, System};
+///
+/// let mut alloc = Proxy {
+///     alloc: System,
+///     callbacks: Validator::new(),
+/// };
+///
+/// let memory = alloc.alloc(Layout::new::<u64>(), AllocInit::Uninitialized)?;
+/// unsafe { alloc.dealloc(memory.ptr, Layout::new::<u64>()) };
+///
+/// let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+///     alloc.dealloc(memory.ptr, Layout::new::<u64>())
+/// }));
+/// assert!(result.is_err());
+/// # Ok::<(), core::alloc::AllocErr>(())
+/// ```
+pub struct Validator {
+    blocks: RefCell<BTreeMap<NonNull<u8>, (usize, Layout)>>,
+    handler: ViolationHandler,
+}
+
+impl Validator {
+    /// Creates a new `Validator`, which panics when a violation is detected.
+    pub const fn new() -> Self {
+        Self::with_handler(panic_on_violation)
+    }
+
+    /// Creates a new `Validator`, which calls `handler` when a violation is detected.
+    ///
+    /// `handler` must not return when used with [`Proxy`], as the invalid call is passed to the
+    /// underlying allocator afterwards. Returning handlers are only sound, if the callbacks are
+    /// invoked directly, e.g. for testing.
+    ///
+    /// [`Proxy`]: crate::Proxy
+    pub const fn with_handler(handler: ViolationHandler) -> Self {
+        Self {
+            blocks: RefCell::new(BTreeMap::new()),
+            handler,
+        }
+    }
+
+    /// Returns the number of blocks currently allocated.
+    pub fn len(&self) -> usize {
+        self.blocks.borrow().len()
+    }
+
+    /// Returns `true` if no block is currently allocated.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.borrow().is_empty()
+    }
+
+    fn insert(&self, memory: MemoryBlock, layout: Layout) {
+        self.blocks
+            .borrow_mut()
+            .insert(memory.ptr, (memory.size, layout));
+    }
+
+    /// Removes the block at `ptr` after validating `layout` and returns its entry, if any.
+    fn remove(
+        &self,
+        operation: Operation,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        location: &'static Location<'static>,
+    ) -> Option<(usize, Layout)> {
+        let entry = self.blocks.borrow_mut().remove(&ptr);
+        let kind = match entry {
+            None => ViolationKind::UnknownBlock,
+            Some((_, old_layout)) if layout.align() != old_layout.align() => {
+                ViolationKind::AlignmentMismatch {
+                    expected: old_layout.align(),
+                    found: layout.align(),
+                }
+            }
+            Some((size, old_layout))
+                if layout.size() < old_layout.size() || layout.size() > size =>
+            {
+                ViolationKind::SizeMismatch {
+                    min: old_layout.size(),
+                    max: size,
+                    found: layout.size(),
+                }
+            }
+            Some(_) => return entry,
+        };
+        self.report(operation, ptr, kind, location);
+        entry
+    }
+
+    /// Replaces the block at `ptr` with the result of `grow` or `shrink`. On failure, the old block
+    /// is still allocated.
+    fn reallocate(
+        &self,
+        operation: Operation,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        let entry = self.remove(operation, ptr, layout, location);
+        match (result, entry) {
+            (Ok(memory), _) => self.insert(memory, unsafe {
+                Layout::from_size_align_unchecked(new_size, layout.align())
+            }),
+            (Err(_), Some(entry)) => {
+                self.blocks.borrow_mut().insert(ptr, entry);
+            }
+            (Err(_), None) => {}
+        }
+    }
+
+    fn report(
+        &self,
+        operation: Operation,
+        ptr: NonNull<u8>,
+        kind: ViolationKind,
+        location: &'static Location<'static>,
+    ) {
+        (self.handler)(&Violation {
+            operation,
+            ptr,
+            kind,
+            location,
+        })
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validator")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+unsafe impl CallbackRef for Validator {
+    fn alloc(
+        &self,
+        layout: Layout,
+        _init: AllocInit,
+        result: Result<MemoryBlock, AllocErr>,
+        _location: &'static Location<'static>,
+    ) {
+        if let Ok(memory) = result {
+            self.insert(memory, layout)
+        }
+    }
+
+    fn dealloc(&self, ptr: NonNull<u8>, layout: Layout, location: &'static Location<'static>) {
+        self.remove(Operation::Dealloc, ptr, layout, location);
+    }
+
+    fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        _placement: ReallocPlacement,
+        _init: AllocInit,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        if new_size < layout.size() {
+            self.report(
+                Operation::Grow,
+                ptr,
+                ViolationKind::InvalidNewSize {
+                    size: layout.size(),
+                    new_size,
+                },
+                location,
+            );
+        }
+        self.reallocate(Operation::Grow, ptr, layout, new_size, result, location)
+    }
+
+    fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+        _placement: ReallocPlacement,
+        result: Result<MemoryBlock, AllocErr>,
+        location: &'static Location<'static>,
+    ) {
+        if new_size > layout.size() {
+            self.report(
+                Operation::Shrink,
+                ptr,
+                ViolationKind::InvalidNewSize {
+                    size: layout.size(),
+                    new_size,
+                },
+                location,
+            );
+        }
+        self.reallocate(Operation::Shrink, ptr, layout, new_size, result, location)
+    }
+
+    fn owns(&self, _success: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::wildcard_imports)]
+    use super::*;
+    use crate::{Proxy, Region};
+    use core::{alloc::AllocRef, cell::Cell};
+
+    thread_local! {
+        static VIOLATION: Cell<Option<ViolationKind>> = Cell::new(None);
+    }
+
+    fn record(violation: &Violation) {
+        VIOLATION.with(|kind| kind.set(Some(violation.kind)));
+    }
+
+    fn take_violation() -> Option<ViolationKind> {
+        VIOLATION.with(Cell::take)
+    }
+
+    #[test]
+    fn valid() {
+        let mut data = [0; 32];
+        let mut alloc = Proxy {
+            alloc: Region::new(&mut data),
+            callbacks: Validator::with_handler(record),
+        };
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            let memory = alloc
+                .grow(
+                    memory.ptr,
+                    Layout::new::<[u8; 8]>(),
+                    16,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect("Could not grow to 16 bytes");
+            let memory = alloc
+                .shrink(
+                    memory.ptr,
+                    Layout::new::<[u8; 16]>(),
+                    4,
+                    ReallocPlacement::MayMove,
+                )
+                .expect("Could not shrink to 4 bytes");
+            assert_eq!(alloc.callbacks.len(), 1);
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 4]>());
+        }
+        assert!(alloc.callbacks.is_empty());
+        assert_eq!(take_violation(), None);
+    }
+
+    #[test]
+    fn double_free() {
+        let mut data = [0; 32];
+        let mut alloc = Proxy {
+            alloc: Region::new(&mut data),
+            callbacks: Validator::with_handler(record),
+        };
+        let layout = Layout::new::<[u8; 8]>();
+
+        let memory = alloc
+            .alloc(layout, AllocInit::Uninitialized)
+            .expect("Could not allocate 8 bytes");
+        unsafe { alloc.dealloc(memory.ptr, layout) };
+        assert_eq!(take_violation(), None);
+
+        // Only pass the second call to the callbacks, as `Region` would catch it as well
+        alloc
+            .callbacks
+            .dealloc(memory.ptr, layout, Location::caller());
+        assert_eq!(take_violation(), Some(ViolationKind::UnknownBlock));
+    }
+
+    #[test]
+    fn layout_mismatch() {
+        let validator = Validator::with_handler(record);
+        let memory = MemoryBlock {
+            ptr: NonNull::dangling(),
+            size: 16,
+        };
+
+        validator.alloc(
+            Layout::new::<u32>(),
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        validator.dealloc(memory.ptr, Layout::new::<[u8; 4]>(), Location::caller());
+        assert_eq!(
+            take_violation(),
+            Some(ViolationKind::AlignmentMismatch {
+                expected: 4,
+                found: 1
+            })
+        );
+
+        validator.alloc(
+            Layout::new::<[u8; 8]>(),
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        validator.dealloc(memory.ptr, Layout::new::<[u8; 16]>(), Location::caller());
+        assert_eq!(take_violation(), None);
+
+        validator.alloc(
+            Layout::new::<[u8; 8]>(),
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        validator.dealloc(memory.ptr, Layout::new::<[u8; 32]>(), Location::caller());
+        assert_eq!(
+            take_violation(),
+            Some(ViolationKind::SizeMismatch {
+                min: 8,
+                max: 16,
+                found: 32
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_new_size() {
+        let mut data = [0; 32];
+        let mut alloc = Proxy {
+            alloc: Region::new(&mut data),
+            callbacks: Validator::with_handler(record),
+        };
+
+        unsafe {
+            let memory = alloc
+                .alloc(Layout::new::<[u8; 8]>(), AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            alloc.callbacks.shrink(
+                memory.ptr,
+                Layout::new::<[u8; 8]>(),
+                16,
+                ReallocPlacement::InPlace,
+                Err(AllocErr),
+                Location::caller(),
+            );
+            assert_eq!(
+                take_violation(),
+                Some(ViolationKind::InvalidNewSize {
+                    size: 8,
+                    new_size: 16
+                })
+            );
+            alloc.dealloc(memory.ptr, Layout::new::<[u8; 8]>());
+        }
+        assert_eq!(take_violation(), None);
+    }
+
+    #[test]
+    fn failed_reallocation() {
+        let mut data = [0; 16];
+        let mut alloc = Proxy {
+            alloc: Region::new(&mut data),
+            callbacks: Validator::with_handler(record),
+        };
+        let layout = Layout::new::<[u8; 8]>();
+
+        unsafe {
+            let memory = alloc
+                .alloc(layout, AllocInit::Uninitialized)
+                .expect("Could not allocate 8 bytes");
+            alloc
+                .grow(
+                    memory.ptr,
+                    layout,
+                    32,
+                    ReallocPlacement::MayMove,
+                    AllocInit::Uninitialized,
+                )
+                .expect_err("Could grow to 32 bytes");
+            assert_eq!(take_violation(), None);
+            assert_eq!(alloc.callbacks.len(), 1);
+
+            alloc.dealloc(memory.ptr, layout);
+            alloc.callbacks.grow(
+                memory.ptr,
+                layout,
+                32,
+                ReallocPlacement::MayMove,
+                AllocInit::Uninitialized,
+                Err(AllocErr),
+                Location::caller(),
+            );
+            assert_eq!(take_violation(), Some(ViolationKind::UnknownBlock));
+
+            alloc.callbacks.shrink(
+                memory.ptr,
+                layout,
+                4,
+                ReallocPlacement::InPlace,
+                Err(AllocErr),
+                Location::caller(),
+            );
+            assert_eq!(take_violation(), Some(ViolationKind::UnknownBlock));
+        }
+        assert!(alloc.callbacks.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "dealloc: `ptr` must denote a block of memory currently allocated")]
+    fn panic_on_double_free() {
+        let validator = Validator::new();
+        let memory = MemoryBlock {
+            ptr: NonNull::dangling(),
+            size: 8,
+        };
+
+        validator.alloc(
+            Layout::new::<u64>(),
+            AllocInit::Uninitialized,
+            Ok(memory),
+            Location::caller(),
+        );
+        validator.dealloc(memory.ptr, Layout::new::<u64>(), Location::caller());
+        validator.dealloc(memory.ptr, Layout::new::<u64>(), Location::caller());
+    }
+}